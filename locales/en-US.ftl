@@ -0,0 +1,211 @@
+## Commands as registered with Discord. Each message is the description of a
+## command or option; translations can also rename it with a `.name` attribute.
+
+command-ping = Pong hopefully.
+command-minesweeper = Clear tiles until you win, but dont hit a mine!
+option-minesweeper-preset = How many mines to hide, from beginner to expert.
+option-minesweeper-width = Tiles across, for a custom board.
+option-minesweeper-height = Tiles down, for a custom board.
+option-minesweeper-mines = Number of mines.
+option-minesweeper-opening = Keep the tiles around your first click free of mines. On for boards of 16 tiles or more.
+option-minesweeper-coop = Let others join to clear the board together.
+option-minesweeper-invite = Invite someone to clear the board with you.
+option-minesweeper-race = Let others join to race you on the same board.
+command-blackjack = Start a game of blackjack.
+command-tictactoe = Play a classic game of tic-tac-toe.
+option-tictactoe-size = Size of tic-tac-toe board.
+option-tictactoe-opponent = Challenge someone to a game.
+option-tictactoe-move_time = Seconds each player has to make a move.
+command-error = Test error.
+command-help = Learn how to play the games.
+option-help-command = Command to explain.
+
+## Any interaction
+
+command-failed =
+    There was an issue running your command.
+    ```{ $error }```
+    Try running it again or report the issue. (Error ID: `{ $id }`)
+component-failed =
+    There was an issue handling your interaction.
+    ```{ $error }```
+    Try again or report the issue. (Error ID: `{ $id }`)
+slow-down = Slow down! Try again in { $seconds ->
+        [one] { $seconds } second
+       *[other] { $seconds } seconds
+    }.
+too-many-games = You already have too many games going, finish one before starting another.
+restarting = The bot is restarting, try again in a minute.
+restart-notice = Bot restarting, your game will resume shortly.
+restart-ended = Bot restarting, your game couldn't be saved and has ended.
+
+## Replays
+
+replay = Replay
+replay-prompt = Watch this game again:
+replay-previous = Previous
+replay-next = Next
+replay-start = **Replay:** start of game ({ $moves ->
+        [one] 1 move
+       *[other] { $moves } moves
+    })
+replay-move = **Replay:** move { $step } of { $moves }
+replay-unavailable = There's nothing to replay in this game.
+
+## Custom games
+
+setup-custom = Custom game
+setup-title = Custom game
+setup-size = Board size
+setup-win_length = Marks in a row to win
+setup-move_time = Seconds per move
+setup-width = Board width
+setup-height = Board height
+setup-mines = Mines
+setup-not-a-number = **{ $field }** must be a whole number.
+setup-out-of-range = **{ $field }** must be between { $min } and { $max }.
+
+## Help
+
+help-title = Commands
+help-overview = Pick a command below to see its options, how to play and some examples.
+help-select = Choose a command
+help-options = Options
+help-rules = How to play
+help-examples = Examples
+help-type-text = text
+help-type-number = number
+help-type-boolean = true or false
+help-type-user = user
+help-minesweeper-rules = Click tiles to clear them. A number tells you how many of the tiles around it hide a mine. Clear every safe tile to win, but clear a mine and you lose.
+help-minesweeper-examples =
+    `/minesweeper` starts a beginner board with 3 mines.
+    `/minesweeper preset:Expert` for a harder one.
+    `/minesweeper width:4 height:3 mines:2` plays on a board of your own.
+    `/minesweeper coop:True` lets others join and clear the board with you.
+    `/minesweeper race:True` races whoever joins, each on a copy of the same board.
+help-blackjack-rules = Get closer to 21 than the dealer without going over. Anyone can join before the game starts.
+help-blackjack-examples = `/blackjack` opens a table for others to join.
+help-tictactoe-rules = Take turns placing your mark. The first to fill a row, column or diagonal wins. You can resign, offer a draw or ask for a rematch. Before anyone joins, **Custom game** changes the board size, how many marks in a row win and the time per move.
+help-tictactoe-examples =
+    `/tictactoe` lets anyone join.
+    `/tictactoe opponent:@someone` challenges someone.
+    `/tictactoe size:4 move_time:30` plays on a bigger board with 30 seconds a move.
+
+## Ping
+
+pong = Pong!
+
+## Blackjack
+
+blackjack-started = { $player } has started a game of blackjack! Who would like to play?
+blackjack-join = Join
+blackjack-start = Start
+
+## Minesweeper
+
+minesweeper-not-yours = Thats not your game! Create your own with `/minesweeper`.
+minesweeper-expired = This game has expired, start a new one with `/minesweeper`.
+minesweeper-won = **You win!**
+    Mines: { $mines }
+    Time: { $seconds }s
+    Level: { $level }
+minesweeper-lost = **Game over.**
+    Mines: { $mines }
+    Cleared: { $cleared }/{ $safe }
+    Time: { $seconds }s
+    Level: { $level }
+minesweeper-best-improved = New personal best by { $seconds }s!
+minesweeper-best-kept = Personal best: { $seconds }s
+minesweeper-beginner = Beginner
+minesweeper-intermediate = Intermediate
+minesweeper-expert = Expert
+minesweeper-custom = Custom { $width }x{ $height }
+minesweeper-too-many-mines = { $safe ->
+        [opening] A { $width }x{ $height } board has room for at most { $max } mines, keeping the first tile you clear and the ones around it safe.
+       *[tile] A { $width }x{ $height } board has room for at most { $max } mines, keeping the first tile you clear safe.
+    }
+minesweeper-coop-started = { $player } started a co-op game of minesweeper! Join to help clear the board.
+minesweeper-coop-invited = { $player } invited { $invited } to clear a minesweeper board together!
+minesweeper-coop-playing = Clearing the board together: { $players }
+minesweeper-players = Players: { $players }
+minesweeper-level = Level: { $level }
+minesweeper-join = Join
+minesweeper-start = Start
+minesweeper-already-joined = You're already in this game!
+minesweeper-not-invited = This game is by invitation only! Start your own with `/minesweeper coop:True`.
+minesweeper-already-started = This game has already started! Start your own with `/minesweeper coop:True`.
+minesweeper-not-started = Wait for the game to start before clearing tiles.
+minesweeper-host-only = Only the player who started this game can do that.
+minesweeper-invite-self = You can't invite yourself! Pick someone else to play with.
+minesweeper-invite-bot = Bots can't play minesweeper! Pick someone else to play with.
+minesweeper-credits = Cleared by: { $credits }
+minesweeper-hit-mine = { $player } hit a mine.
+minesweeper-already-cleared = That tile has already been cleared!
+minesweeper-over = This game is already over!
+minesweeper-coop-or-race = Pick either a co-op game or a race, not both.
+minesweeper-race-started = { $player } started a minesweeper race! Join to race them on the same board.
+minesweeper-race-invited = { $player } challenged { $invited } to a minesweeper race!
+minesweeper-race-alone = A race needs at least two players! Wait for someone to join.
+minesweeper-race-playing = **The race is on!** Press Play to get your board.
+minesweeper-race-won = **{ $player } wins the race!**
+minesweeper-race-no-winner = **Everyone hit a mine!** Nobody wins this race.
+minesweeper-race-progress = { $player }: { $cleared }/{ $safe } cleared
+minesweeper-race-cleared = { $player }: cleared in { $seconds }s
+minesweeper-race-hit-mine = { $player }: hit a mine after { $seconds }s, { $cleared }/{ $safe } cleared
+minesweeper-race-play = Play
+minesweeper-race-board = Your board. Clear it before anyone else does!
+minesweeper-race-over = This race is over! Start a new one with `/minesweeper race:True`.
+
+## Tic-tac-toe
+
+tictactoe-started = { $player } has started a game of tic-tac-toe! Who would like to play?
+tictactoe-challenged = { $player } has challenged { $opponent } to a game of tic-tac-toe!
+tictactoe-challenge-self = You can't challenge yourself! Pick someone else to play with.
+tictactoe-challenge-bot = Bots can't play tic-tac-toe! Pick someone else to play with.
+tictactoe-challenge-expired = { $player }'s challenge to { $opponent } has expired.
+tictactoe-challenge-declined = { $opponent } declined { $player }'s challenge.
+tictactoe-challenge-cancelled = { $player } cancelled the challenge.
+tictactoe-accept = Accept
+tictactoe-decline = Decline
+tictactoe-join = Join
+tictactoe-custom-host = Only the player who started this game can change it.
+tictactoe-win-length-too-long = Marks in a row to win can't be more than the board size ({ $size }).
+tictactoe-settings = Board: { $size }x{ $size }, { $length } in a row to win, { $seconds } seconds per move.
+tictactoe-expired = This game has expired, start a new one with `/tictactoe`.
+tictactoe-not-for-you = This game isn't for you! Create your own with `/tictactoe`.
+tictactoe-join-own = You can't join your own game! Find someone else to play with.
+tictactoe-already-joined = Someone already joined this game! You can create your own with `/tictactoe`.
+tictactoe-not-yours = That's not your game! Create your own with `/tictactoe`.
+tictactoe-not-your-turn = It's not your turn! Wait for the other player to make a move.
+tictactoe-taken = That square has already been taken! Pick another one.
+tictactoe-first-turn = { $player }'s turn!
+tictactoe-turn = { $player }'s turn.
+tictactoe-deadline = Make your move { $time }.
+tictactoe-won = { $player } won!
+tictactoe-tie = It's a tie!
+tictactoe-timeout = { $player } ran out of time! { $winner } wins.
+tictactoe-resigned = { $player } resigned! { $winner } wins.
+tictactoe-series = Series: { $player1 } { $wins1 } - { $wins2 } { $player2 }
+tictactoe-ties = ({ $ties ->
+        [one] 1 tie
+       *[other] { $ties } ties
+    })
+tictactoe-controls = Game controls
+tictactoe-resign = Resign
+tictactoe-offer-draw = Offer Draw
+tictactoe-accept-draw = Accept Draw
+tictactoe-decline-draw = Decline Draw
+tictactoe-draw-offered = { $player } offered a draw.
+tictactoe-draw-pending = A draw has already been offered!
+tictactoe-draw-agreed = It's a draw by agreement!
+tictactoe-draw-own = You can't accept your own draw offer! Waiting for the other player.
+tictactoe-draw-withdrawn = { $player } withdrew their draw offer.
+tictactoe-draw-declined = { $player } declined the draw.
+tictactoe-no-draw = There's no draw offer to answer.
+tictactoe-over = This game is already over!
+tictactoe-rematch = Rematch
+tictactoe-rematch-asked = { $player } wants a rematch!
+tictactoe-rematch-again = You've already asked for a rematch! Waiting for the other player.
+tictactoe-rematch-started = Rematch! { $player }'s turn!
+tictactoe-rematch-underway = That rematch has already started!
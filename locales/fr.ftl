@@ -0,0 +1,225 @@
+## Commandes
+
+command-ping = Pong, normalement.
+command-minesweeper = Dégage les cases jusqu'à la victoire, mais évite les mines !
+    .name = démineur
+option-minesweeper-preset = Nombre de mines cachées, de débutant à expert.
+    .name = difficulté
+option-minesweeper-width = Cases en largeur, pour une grille personnalisée.
+    .name = largeur
+option-minesweeper-height = Cases en hauteur, pour une grille personnalisée.
+    .name = hauteur
+option-minesweeper-mines = Nombre de mines.
+option-minesweeper-opening = Garde les cases autour de ton premier clic sans mines. Activé pour les grilles de 16 cases ou plus.
+    .name = ouverture
+option-minesweeper-coop = Laisse d'autres joueurs rejoindre pour dégager la grille ensemble.
+    .name = coop
+option-minesweeper-invite = Invite quelqu'un à dégager la grille avec toi.
+    .name = inviter
+option-minesweeper-race = Laisse d'autres joueurs rejoindre pour faire la course sur la même grille.
+    .name = course
+command-blackjack = Lance une partie de blackjack.
+command-tictactoe = Joue une partie classique de morpion.
+    .name = morpion
+option-tictactoe-size = Taille de la grille.
+    .name = taille
+option-tictactoe-opponent = Défie quelqu'un.
+    .name = adversaire
+option-tictactoe-move_time = Secondes dont chaque joueur dispose pour jouer.
+    .name = temps_par_coup
+command-error = Erreur de test.
+    .name = erreur
+command-help = Apprends à jouer aux jeux.
+    .name = aide
+option-help-command = Commande à expliquer.
+    .name = commande
+
+## Toutes les interactions
+
+command-failed =
+    Un problème est survenu pendant ta commande.
+    ```{ $error }```
+    Réessaie ou signale le problème. (ID de l'erreur : `{ $id }`)
+component-failed =
+    Un problème est survenu pendant ton interaction.
+    ```{ $error }```
+    Réessaie ou signale le problème. (ID de l'erreur : `{ $id }`)
+slow-down = Doucement ! Réessaie dans { $seconds ->
+        [one] { $seconds } seconde
+       *[other] { $seconds } secondes
+    }.
+too-many-games = Tu as déjà trop de parties en cours, termines-en une avant d'en lancer une autre.
+restarting = Le bot redémarre, réessaie dans une minute.
+restart-notice = Le bot redémarre, ta partie reprendra bientôt.
+restart-ended = Le bot redémarre, ta partie n'a pas pu être sauvegardée et s'est terminée.
+
+## Replays
+
+replay = Revoir
+replay-prompt = Revois cette partie :
+replay-previous = Précédent
+replay-next = Suivant
+replay-start = **Replay :** début de partie ({ $moves ->
+        [one] 1 coup
+       *[other] { $moves } coups
+    })
+replay-move = **Replay :** coup { $step } sur { $moves }
+replay-unavailable = Il n'y a rien à revoir dans cette partie.
+
+## Parties personnalisées
+
+setup-custom = Partie personnalisée
+setup-title = Partie personnalisée
+setup-size = Taille de la grille
+setup-win_length = Symboles alignés pour gagner
+setup-move_time = Secondes par coup
+setup-width = Largeur de la grille
+setup-height = Hauteur de la grille
+setup-mines = Mines
+setup-not-a-number = **{ $field }** doit être un nombre entier.
+setup-out-of-range = **{ $field }** doit être entre { $min } et { $max }.
+
+## Aide
+
+help-title = Commandes
+help-overview = Choisis une commande ci-dessous pour voir ses options, comment jouer et quelques exemples.
+help-select = Choisis une commande
+help-options = Options
+help-rules = Comment jouer
+help-examples = Exemples
+help-type-text = texte
+help-type-number = nombre
+help-type-boolean = vrai ou faux
+help-type-user = membre
+help-minesweeper-rules = Clique sur les cases pour les dégager. Un chiffre indique combien de cases autour cachent une mine. Dégage toutes les cases sûres pour gagner, mais si tu tombes sur une mine, tu perds.
+help-minesweeper-examples =
+    `/démineur` lance une grille débutant avec 3 mines.
+    `/démineur difficulté:Expert` pour une partie plus difficile.
+    `/démineur largeur:4 hauteur:3 mines:2` joue sur une grille à ta façon.
+    `/démineur coop:True` laisse d'autres joueurs dégager la grille avec toi.
+    `/démineur course:True` fait la course contre ceux qui rejoignent, chacun sur une copie de la même grille.
+help-blackjack-rules = Approche-toi plus de 21 que le croupier sans dépasser. Tout le monde peut rejoindre avant le début de la partie.
+help-blackjack-examples = `/blackjack` ouvre une table que les autres peuvent rejoindre.
+help-tictactoe-rules = Placez vos symboles chacun votre tour. Le premier à remplir une ligne, une colonne ou une diagonale gagne. Tu peux abandonner, proposer le nul ou demander une revanche. Avant que quelqu'un rejoigne, **Partie personnalisée** change la taille de la grille, le nombre de symboles alignés pour gagner et le temps par coup.
+help-tictactoe-examples =
+    `/morpion` permet à n'importe qui de rejoindre.
+    `/morpion adversaire:@quelqu'un` défie quelqu'un.
+    `/morpion taille:4 temps_par_coup:30` joue sur une grille plus grande avec 30 secondes par coup.
+
+## Ping
+
+pong = Pong !
+
+## Blackjack
+
+blackjack-started = { $player } a lancé une partie de blackjack ! Qui veut jouer ?
+blackjack-join = Rejoindre
+blackjack-start = Commencer
+
+## Démineur
+
+minesweeper-not-yours = Ce n'est pas ta partie ! Lance la tienne avec `/démineur`.
+minesweeper-expired = Cette partie a expiré, lances-en une nouvelle avec `/démineur`.
+minesweeper-won = **Gagné !**
+    Mines : { $mines }
+    Temps : { $seconds } s
+    Niveau : { $level }
+minesweeper-lost = **Perdu.**
+    Mines : { $mines }
+    Dégagées : { $cleared }/{ $safe }
+    Temps : { $seconds } s
+    Niveau : { $level }
+minesweeper-best-improved = Nouveau record personnel, battu de { $seconds } s !
+minesweeper-best-kept = Record personnel : { $seconds } s
+minesweeper-beginner = Débutant
+minesweeper-intermediate = Intermédiaire
+minesweeper-expert = Expert
+minesweeper-custom = Personnalisé { $width }x{ $height }
+minesweeper-too-many-mines = { $safe ->
+        [opening] Une grille de { $width }x{ $height } peut contenir au plus { $max } mines, pour que la première case dégagée et ses voisines restent sûres.
+       *[tile] Une grille de { $width }x{ $height } peut contenir au plus { $max } mines, pour que la première case dégagée reste sûre.
+    }
+minesweeper-coop-started = { $player } a lancé une partie de démineur en coop ! Rejoins-la pour aider à dégager la grille.
+minesweeper-coop-invited = { $player } invite { $invited } à dégager une grille de démineur ensemble !
+minesweeper-coop-playing = Dégagement de la grille ensemble : { $players }
+minesweeper-players = Joueurs : { $players }
+minesweeper-level = Niveau : { $level }
+minesweeper-join = Rejoindre
+minesweeper-start = Commencer
+minesweeper-already-joined = Tu es déjà dans cette partie !
+minesweeper-not-invited = Cette partie est sur invitation ! Lance la tienne avec `/démineur coop:True`.
+minesweeper-already-started = Cette partie a déjà commencé ! Lance la tienne avec `/démineur coop:True`.
+minesweeper-not-started = Attends le début de la partie avant de dégager des cases.
+minesweeper-host-only = Seul le joueur qui a lancé cette partie peut faire ça.
+minesweeper-invite-self = Tu ne peux pas t'inviter toi-même ! Choisis quelqu'un d'autre.
+minesweeper-invite-bot = Les bots ne jouent pas au démineur ! Choisis quelqu'un d'autre.
+minesweeper-credits = Dégagées par : { $credits }
+minesweeper-hit-mine = { $player } est tombé sur une mine.
+minesweeper-already-cleared = Cette case a déjà été dégagée !
+minesweeper-over = Cette partie est déjà terminée !
+minesweeper-coop-or-race = Choisis entre une partie en coop et une course, pas les deux.
+minesweeper-race-started = { $player } a lancé une course de démineur ! Rejoins-la pour faire la course sur la même grille.
+minesweeper-race-invited = { $player } défie { $invited } à une course de démineur !
+minesweeper-race-alone = Une course se joue à deux au moins ! Attends que quelqu'un rejoigne.
+minesweeper-race-playing = **La course est lancée !** Appuie sur Jouer pour avoir ta grille.
+minesweeper-race-won = **{ $player } remporte la course !**
+minesweeper-race-no-winner = **Tout le monde est tombé sur une mine !** Personne ne remporte cette course.
+minesweeper-race-progress = { $player } : { $cleared }/{ $safe } dégagées
+minesweeper-race-cleared = { $player } : grille dégagée en { $seconds } s
+minesweeper-race-hit-mine = { $player } : tombé sur une mine après { $seconds } s, { $cleared }/{ $safe } dégagées
+minesweeper-race-play = Jouer
+minesweeper-race-board = Ta grille. Dégage-la avant les autres !
+minesweeper-race-over = Cette course est terminée ! Lances-en une nouvelle avec `/démineur course:True`.
+
+## Morpion
+
+tictactoe-started = { $player } a lancé une partie de morpion ! Qui veut jouer ?
+tictactoe-challenged = { $player } défie { $opponent } au morpion !
+tictactoe-challenge-self = Tu ne peux pas te défier toi-même ! Choisis quelqu'un d'autre.
+tictactoe-challenge-bot = Les bots ne jouent pas au morpion ! Choisis quelqu'un d'autre.
+tictactoe-challenge-expired = Le défi de { $player } à { $opponent } a expiré.
+tictactoe-challenge-declined = { $opponent } a refusé le défi de { $player }.
+tictactoe-challenge-cancelled = { $player } a annulé le défi.
+tictactoe-accept = Accepter
+tictactoe-decline = Refuser
+tictactoe-join = Rejoindre
+tictactoe-custom-host = Seul le joueur qui a lancé cette partie peut la modifier.
+tictactoe-win-length-too-long = Le nombre de symboles alignés pour gagner ne peut pas dépasser la taille de la grille ({ $size }).
+tictactoe-settings = Grille : { $size }x{ $size }, { $length } alignés pour gagner, { $seconds } secondes par coup.
+tictactoe-expired = Cette partie a expiré, lances-en une nouvelle avec `/morpion`.
+tictactoe-not-for-you = Cette partie n'est pas pour toi ! Lance la tienne avec `/morpion`.
+tictactoe-join-own = Tu ne peux pas rejoindre ta propre partie ! Trouve quelqu'un d'autre.
+tictactoe-already-joined = Quelqu'un a déjà rejoint cette partie ! Tu peux lancer la tienne avec `/morpion`.
+tictactoe-not-yours = Ce n'est pas ta partie ! Lance la tienne avec `/morpion`.
+tictactoe-not-your-turn = Ce n'est pas ton tour ! Attends que l'autre joueur joue.
+tictactoe-taken = Cette case est déjà prise ! Choisis-en une autre.
+tictactoe-first-turn = À { $player } de jouer !
+tictactoe-turn = À { $player } de jouer.
+tictactoe-deadline = Joue { $time }.
+tictactoe-won = { $player } a gagné !
+tictactoe-tie = Match nul !
+tictactoe-timeout = { $player } n'a plus de temps ! { $winner } gagne.
+tictactoe-resigned = { $player } abandonne ! { $winner } gagne.
+tictactoe-series = Série : { $player1 } { $wins1 } - { $wins2 } { $player2 }
+tictactoe-ties = ({ $ties ->
+        [one] 1 nul
+       *[other] { $ties } nuls
+    })
+tictactoe-controls = Contrôles de la partie
+tictactoe-resign = Abandonner
+tictactoe-offer-draw = Proposer le nul
+tictactoe-accept-draw = Accepter le nul
+tictactoe-decline-draw = Refuser le nul
+tictactoe-draw-offered = { $player } propose le nul.
+tictactoe-draw-pending = Le nul a déjà été proposé !
+tictactoe-draw-agreed = Match nul d'un commun accord !
+tictactoe-draw-own = Tu ne peux pas accepter ta propre proposition ! Attends l'autre joueur.
+tictactoe-draw-withdrawn = { $player } a retiré sa proposition de nul.
+tictactoe-draw-declined = { $player } a refusé le nul.
+tictactoe-no-draw = Aucune proposition de nul en attente.
+tictactoe-over = Cette partie est déjà terminée !
+tictactoe-rematch = Revanche
+tictactoe-rematch-asked = { $player } veut une revanche !
+tictactoe-rematch-again = Tu as déjà demandé une revanche ! Attends l'autre joueur.
+tictactoe-rematch-started = Revanche ! À { $player } de jouer !
+tictactoe-rematch-underway = Cette revanche a déjà commencé !
@@ -1,25 +1,26 @@
 use std::collections::HashMap;
 
-use serenity::model::{
-    id::UserId,
-    interactions::{
-        application_command::ApplicationCommandInteraction,
-        message_component::{ButtonStyle, MessageComponentInteraction},
-    },
+use serenity::builder::{
+    CreateActionRow, CreateAllowedMentions, CreateButton, CreateInteractionResponse,
+    CreateInteractionResponseMessage,
 };
+use serenity::model::application::{ButtonStyle, CommandInteraction, ComponentInteraction};
+use serenity::model::id::UserId;
 use serenity::prelude::*;
 
-use tracing::info;
-
+use crate::commands::responder::Responder;
+use crate::locale::{tr, Locale};
 use crate::Error;
 
+#[allow(dead_code)]
 enum Suit {
     Spades,
     Clubs,
     Hearts,
-    Diamonds
+    Diamonds,
 }
 
+#[allow(dead_code)]
 enum Number {
     Ace,
     Two,
@@ -33,18 +34,21 @@ enum Number {
     Ten,
     Jack,
     Queen,
-    King
+    King,
 }
 
+#[allow(dead_code)]
 struct Card {
     suit: Suit,
-    number: Number
+    number: Number,
 }
 
+#[allow(dead_code)]
 pub struct BlackjackGame {
     players: Vec<UserId>,
 }
 
+#[allow(dead_code)]
 pub struct BlackjackGames;
 
 impl TypeMapKey for BlackjackGames {
@@ -52,45 +56,35 @@ impl TypeMapKey for BlackjackGames {
 }
 
 pub async fn blackjack(
-    ctx: &Context,
-    command: &ApplicationCommandInteraction,
+    responder: &impl Responder,
+    command: &CommandInteraction,
 ) -> Result<(), Error> {
     let name = command.user.mention();
+    let locale = Locale::of_guild(command.guild_locale.as_deref(), &command.locale);
 
-    command
-        .create_interaction_response(&ctx.http, |response| {
-            response.interaction_response_data(|data| {
-                data.content(format!(
-                    "{} has started a game of blackjack! Who would like to play?",
-                    name
-                ))
-                .allowed_mentions(|mentions| mentions.empty_users())
-                .components(|components| {
-                    components.create_action_row(|row| {
-                        row.create_button(|button| {
-                            button
-                                .label("Join")
-                                .custom_id("blackjack-join")
-                                .style(ButtonStyle::Success)
-                        })
-                        .create_button(|button| {
-                            button
-                                .label("Start")
-                                .custom_id("blackjack-start")
-                                .style(ButtonStyle::Secondary)
-                                .disabled(true)
-                        })
-                    })
-                })
-            })
-        })
+    responder
+        .create_interaction_response(CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new()
+                .content(tr!(locale, "blackjack-started", player = name.to_string()))
+                .allowed_mentions(CreateAllowedMentions::new().empty_users())
+                .components(vec![CreateActionRow::Buttons(vec![
+                    CreateButton::new("blackjack-join")
+                        .label(tr!(locale, "blackjack-join"))
+                        .style(ButtonStyle::Success),
+                    CreateButton::new("blackjack-start")
+                        .label(tr!(locale, "blackjack-start"))
+                        .style(ButtonStyle::Secondary)
+                        .disabled(true),
+                ])]),
+        ))
         .await?;
     Ok(())
 }
 
 pub async fn blackjack_button(
-    _ctx: &Context,
-    _component: &MessageComponentInteraction,
+    _data: &RwLock<TypeMap>,
+    _responder: &impl Responder,
+    _component: &ComponentInteraction,
 ) -> Result<(), Error> {
     Err(Error::from("Blackjack button."))
 }
@@ -1,9 +1,8 @@
-use serenity::model::interactions::application_command::ApplicationCommandInteraction;
-use serenity::prelude::*;
+use serenity::model::application::CommandInteraction;
 
 use crate::Error;
 
-pub async fn fail(_ctx: &Context, command: &ApplicationCommandInteraction) -> Result<(), Error> {
+pub async fn fail(command: &CommandInteraction) -> Result<(), Error> {
     Err(Error::from(format!(
         "Test error from {}",
         command.member.as_ref().unwrap().display_name()
@@ -0,0 +1,292 @@
+//! `/help`, built from the same definitions the commands are registered
+//! with so it always matches what Discord offers.
+
+use serenity::builder::{
+    CreateActionRow, CreateEmbed, CreateInteractionResponse, CreateInteractionResponseMessage,
+    CreateSelectMenu, CreateSelectMenuKind, CreateSelectMenuOption,
+};
+use serenity::json::Value;
+use serenity::model::application::{
+    CommandInteraction, ComponentInteraction, ComponentInteractionDataKind, ResolvedValue,
+};
+
+use crate::commands::responder::Responder;
+use crate::commands::{definitions, is_public};
+use crate::locale::{tr, Locale};
+use crate::Error;
+
+/// A command's name or description in `locale`, falling back to the one
+/// registered by default.
+fn field<'a>(command: &'a Value, field: &str, locale: Locale) -> &'a str {
+    command[format!("{}_localizations", field).as_str()][locale.code()]
+        .as_str()
+        .or_else(|| command[field].as_str())
+        .unwrap_or_default()
+}
+
+/// What can be passed to an option, like `number, 1-16`, or its choices.
+fn option_kind(option: &Value, locale: Locale) -> String {
+    if let Some(choices) = option["choices"].as_array().filter(|c| !c.is_empty()) {
+        return choices
+            .iter()
+            .map(|choice| field(choice, "name", locale))
+            .collect::<Vec<_>>()
+            .join(", ");
+    }
+    let kind = match option["type"].as_u64() {
+        Some(4) => tr!(locale, "help-type-number"),
+        Some(5) => tr!(locale, "help-type-boolean"),
+        Some(6) => tr!(locale, "help-type-user"),
+        _ => tr!(locale, "help-type-text"),
+    };
+    match (option["min_value"].as_i64(), option["max_value"].as_i64()) {
+        (Some(min), Some(max)) => format!("{}, {}-{}", kind, min, max),
+        _ => kind,
+    }
+}
+
+/// Every command but `/help` itself and those only some members can use, in
+/// the order they are registered.
+fn commands() -> Vec<Value> {
+    definitions()
+        .into_iter()
+        .filter(|command| command["name"] != "help" && is_public(command))
+        .collect()
+}
+
+/// An overview of every command, or the details of `chosen`, with a menu to
+/// pick another.
+fn render(chosen: Option<&str>, locale: Locale) -> (CreateEmbed, Vec<CreateActionRow>) {
+    let commands = commands();
+    let chosen = chosen.and_then(|name| commands.iter().find(|command| command["name"] == name));
+
+    let embed = match chosen {
+        Some(command) => {
+            let name = command["name"].as_str().unwrap();
+            let mut embed = CreateEmbed::new()
+                .title(format!("/{}", field(command, "name", locale)))
+                .description(field(command, "description", locale));
+
+            let options = command["options"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .map(|option| {
+                    format!(
+                        "`{}` ({}): {}",
+                        field(option, "name", locale),
+                        option_kind(option, locale),
+                        field(option, "description", locale)
+                    )
+                })
+                .collect::<Vec<_>>();
+            if !options.is_empty() {
+                embed = embed.field(tr!(locale, "help-options"), options.join("\n"), false);
+            }
+            if let Some(rules) = locale.find(&format!("help-{}-rules", name), None) {
+                embed = embed.field(tr!(locale, "help-rules"), rules, false);
+            }
+            if let Some(examples) = locale.find(&format!("help-{}-examples", name), None) {
+                embed = embed.field(tr!(locale, "help-examples"), examples, false);
+            }
+            embed
+        }
+        None => CreateEmbed::new()
+            .title(tr!(locale, "help-title"))
+            .description(tr!(locale, "help-overview"))
+            .fields(commands.iter().map(|command| {
+                (
+                    format!("/{}", field(command, "name", locale)),
+                    field(command, "description", locale),
+                    false,
+                )
+            })),
+    };
+
+    let options = commands
+        .iter()
+        .map(|command| {
+            CreateSelectMenuOption::new(
+                format!("/{}", field(command, "name", locale)),
+                command["name"].as_str().unwrap(),
+            )
+            .description(field(command, "description", locale))
+            .default_selection(chosen == Some(command))
+        })
+        .collect();
+    let menu = CreateSelectMenu::new("help", CreateSelectMenuKind::String { options })
+        .placeholder(tr!(locale, "help-select"));
+    (embed, vec![CreateActionRow::SelectMenu(menu)])
+}
+
+pub async fn help(responder: &impl Responder, command: &CommandInteraction) -> Result<(), Error> {
+    let locale = Locale::of_user(&command.locale);
+    let chosen = command
+        .data
+        .options()
+        .into_iter()
+        .find_map(|option| match option.value {
+            ResolvedValue::String(name) => Some(name),
+            _ => None,
+        });
+    let (embed, components) = render(chosen, locale);
+
+    responder
+        .create_interaction_response(CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new()
+                .add_embed(embed)
+                .components(components)
+                .ephemeral(true),
+        ))
+        .await
+}
+
+/// Shows the command picked from the menu under `/help`.
+pub async fn help_select(
+    responder: &impl Responder,
+    component: &ComponentInteraction,
+) -> Result<(), Error> {
+    let locale = Locale::of_user(&component.locale);
+    let chosen = match &component.data.kind {
+        ComponentInteractionDataKind::StringSelect { values } => values.first(),
+        _ => None,
+    };
+    let (embed, components) = render(chosen.map(String::as_str), locale);
+
+    responder
+        .create_interaction_response(CreateInteractionResponse::UpdateMessage(
+            CreateInteractionResponseMessage::new()
+                .add_embed(embed)
+                .components(components),
+        ))
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use serenity::json::json;
+    use serenity::model::id::UserId;
+
+    use super::*;
+    use crate::commands::responder::testing::*;
+
+    const USER: UserId = UserId::new(10);
+
+    fn menu(response: &Value) -> &Value {
+        &response["data"]["components"][0]["components"][0]
+    }
+
+    #[tokio::test]
+    async fn lists_every_command() {
+        let responder = RecordingResponder::default();
+        let command = command_interaction("help", USER, 1, json!([]), json!({}));
+        help(&responder, &command).await.unwrap();
+
+        let response = responder.last();
+        assert_eq!(response["data"]["flags"], 64);
+        let embed = &response["data"]["embeds"][0];
+        let fields = embed["fields"].as_array().unwrap();
+        let names = fields
+            .iter()
+            .map(|field| field["name"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["/ping", "/minesweeper", "/blackjack", "/tictactoe"]);
+        assert_eq!(fields[3]["value"], "Play a classic game of tic-tac-toe.");
+
+        let menu = menu(&response);
+        assert_eq!(menu["custom_id"], "help");
+        assert_eq!(menu["options"].as_array().unwrap().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn describes_options_from_the_definitions() {
+        let responder = RecordingResponder::default();
+        let command = command_interaction(
+            "help",
+            USER,
+            1,
+            json!([{"name": "command", "type": 3, "value": "tictactoe"}]),
+            json!({}),
+        );
+        help(&responder, &command).await.unwrap();
+
+        let response = responder.last();
+        let embed = &response["data"]["embeds"][0];
+        assert_eq!(embed["title"], "/tictactoe");
+        assert_eq!(embed["fields"][0]["name"], "Options");
+        assert_eq!(
+            embed["fields"][0]["value"],
+            "`size` (number, 2-5): Size of tic-tac-toe board.\n\
+             `opponent` (user): Challenge someone to a game.\n\
+             `move_time` (number, 10-600): Seconds each player has to make a move."
+        );
+        assert_eq!(embed["fields"][1]["name"], "How to play");
+        assert_eq!(embed["fields"][2]["name"], "Examples");
+        assert_eq!(menu(&response)["options"][3]["default"], true);
+    }
+
+    #[tokio::test]
+    async fn menu_drills_into_a_command() {
+        let responder = RecordingResponder::default();
+        let mut component = component_interaction("help", USER, 1, USER);
+        component.data.kind = ComponentInteractionDataKind::StringSelect {
+            values: vec!["minesweeper".to_string()],
+        };
+        help_select(&responder, &component).await.unwrap();
+
+        let response = responder.last();
+        assert_eq!(response["type"], 7);
+        let embed = &response["data"]["embeds"][0];
+        assert_eq!(embed["title"], "/minesweeper");
+        assert_eq!(
+            embed["fields"][0]["value"],
+            "`preset` (Beginner, Intermediate, Expert): How many mines to hide, from beginner to expert.\n\
+             `width` (number, 3-5): Tiles across, for a custom board.\n\
+             `height` (number, 3-5): Tiles down, for a custom board.\n\
+             `mines` (number, 1-24): Number of mines.\n\
+             `opening` (true or false): Keep the tiles around your first click free of mines. On for boards of 16 tiles or more.\n\
+             `coop` (true or false): Let others join to clear the board together.\n\
+             `invite` (user): Invite someone to clear the board with you.\n\
+             `race` (true or false): Let others join to race you on the same board."
+        );
+
+        // Commands without rules leave them out
+        component.data.kind = ComponentInteractionDataKind::StringSelect {
+            values: vec!["ping".to_string()],
+        };
+        help_select(&responder, &component).await.unwrap();
+        let embed = &responder.last()["data"]["embeds"][0];
+        assert_eq!(embed["title"], "/ping");
+        assert!(embed["fields"].is_null());
+    }
+
+    #[test]
+    fn admin_commands_are_left_out() {
+        let error = definitions()
+            .into_iter()
+            .find(|command| command["name"] == "error")
+            .unwrap();
+        assert_eq!(error["default_member_permissions"], "8");
+        assert!(!is_public(&error));
+        let (overview, _) = render(None, Locale::default());
+        assert_eq!(render(Some("error"), Locale::default()).0, overview);
+    }
+
+    #[test]
+    fn help_offers_every_other_command() {
+        let definitions = definitions();
+        let help = definitions.last().unwrap();
+        assert_eq!(help["name"], "help");
+        let choices = help["options"][0]["choices"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|choice| choice["value"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(choices, ["ping", "minesweeper", "blackjack", "tictactoe"]);
+        assert_eq!(
+            help["options"][0]["choices"][3]["name_localizations"],
+            json!({"fr": "morpion"})
+        );
+    }
+}
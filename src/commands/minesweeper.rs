@@ -1,158 +1,584 @@
-use std::collections::{HashMap, HashSet};
-use std::iter::repeat_with;
-use std::time::Instant;
+use std::collections::HashMap;
+use std::iter;
+use std::time::{Duration, SystemTime};
 
-use rand::seq::SliceRandom;
-
-use serenity::builder::CreateComponents;
+use serde::{Deserialize, Serialize};
+use serenity::builder::{
+    CreateActionRow, CreateAllowedMentions, CreateAttachment, CreateButton,
+    CreateInteractionResponse, CreateInteractionResponseFollowup, CreateInteractionResponseMessage,
+    CreateModal, EditInteractionResponse,
+};
+use serenity::model::application::{
+    ButtonStyle, CommandInteraction, ComponentInteraction, ModalInteraction, ResolvedValue,
+};
 use serenity::model::channel::ReactionType;
 use serenity::model::id::UserId;
-use serenity::model::interactions::{
-    application_command::ApplicationCommandInteraction,
-    application_command::ApplicationCommandInteractionDataOptionValue,
-    message_component::ButtonStyle, message_component::MessageComponentInteraction,
-    InteractionResponseType,
-};
 use serenity::prelude::*;
 
+use crate::commands::replay::{
+    cell_name, click_time, export, export_file, format_seconds, render_steps, step_heading,
+    timed_moves, unix_time, MoveRecord,
+};
+use crate::commands::responder::Responder;
+use crate::commands::setup::{custom_button, modal, read, Field};
+use crate::games::minesweeper::{
+    apply_move, MinesweeperAction, MinesweeperBoard, MinesweeperCell, MinesweeperOutcome,
+};
+use crate::games::MoveError;
+use crate::locale::{tr, Locale};
+use crate::records::{Best, Records, RecordsContainer};
+use crate::store::{Game, GameId, GameStore};
 use crate::Error;
 
 /*
    TODO:
        - Flagging with command.message.reactions
        - Get get player from command.message.interaction.user
-       - Store board as a Vec of Bomb/Safe
        - Store game state as a Vec of Flagged/Checked/None
-       - Change game key from String to InteractionId
 */
 
-pub enum MinesweeperCell {
-    Safe,
-    Checked,
-    Bomb,
+/// The largest board that fits on a message, with a button per tile.
+const MAX_WIDTH: usize = 5;
+const MAX_HEIGHT: usize = 5;
+
+/// Boards with at least this many tiles keep the first reveal's neighbours
+/// free of mines, unless told otherwise.
+const OPENING_MIN_TILES: usize = 16;
+
+/// Ready-made boards offered by `/minesweeper preset`, each with more mines
+/// than the last.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Preset {
+    Beginner,
+    Intermediate,
+    Expert,
+}
+
+impl Preset {
+    pub const ALL: [Preset; 3] = [Preset::Beginner, Preset::Intermediate, Preset::Expert];
+
+    /// The choice's value, also naming its `minesweeper-<name>` message.
+    pub fn name(self) -> &'static str {
+        match self {
+            Preset::Beginner => "beginner",
+            Preset::Intermediate => "intermediate",
+            Preset::Expert => "expert",
+        }
+    }
+
+    /// Width, height and mines.
+    fn board(self) -> (usize, usize, usize) {
+        match self {
+            Preset::Beginner => (MAX_WIDTH, MAX_HEIGHT, 3),
+            Preset::Intermediate => (MAX_WIDTH, MAX_HEIGHT, 6),
+            Preset::Expert => (MAX_WIDTH, MAX_HEIGHT, 10),
+        }
+    }
 }
 
+/// A player's own board in a race, dealt the same as everyone else's.
+#[derive(Clone, Serialize, Deserialize)]
+struct Racer {
+    board: MinesweeperBoard,
+    /// How long after the start they cleared their board or hit a mine.
+    time: Option<Duration>,
+}
+
+impl Racer {
+    /// Safe tiles cleared, out of how many there are.
+    fn progress(&self) -> (usize, usize) {
+        let board = &self.board;
+        let safe = board.width() * board.height() - board.mines();
+        (safe - board.safe_remaining(), safe)
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct MinesweeperGame {
     player: UserId,
-    mines: usize,
-    start_time: Option<Instant>,
-    board: Option<Vec<MinesweeperCell>>,
+    /// When the first tile was clicked, or a race was started.
+    start_time: Option<SystemTime>,
+    board: MinesweeperBoard,
+    history: Vec<MoveRecord<MinesweeperAction>>,
+    /// Token of the last interaction to update the board.
+    #[serde(default)]
+    token: Option<String>,
+    #[serde(default)]
+    locale: Locale,
+    /// Whether others can join to clear the board together.
+    #[serde(default)]
+    coop: bool,
+    /// The only player who can join a co-op game, if someone was invited.
+    #[serde(default)]
+    invited: Option<UserId>,
+    /// Players who joined a co-op game or race.
+    #[serde(default)]
+    helpers: Vec<UserId>,
+    /// Whether everyone gets a board of their own, the first to clear it
+    /// winning.
+    #[serde(default)]
+    race: bool,
+    /// Each player's board once a race has started.
+    #[serde(default)]
+    racers: HashMap<UserId, Racer>,
+    /// The first player to clear their board in a race.
+    #[serde(default)]
+    winner: Option<UserId>,
+    /// Whether a co-op game or race is still waiting for players to join.
+    #[serde(default)]
+    lobby: bool,
+    /// Tiles cleared by each player.
+    #[serde(default)]
+    cleared: HashMap<UserId, usize>,
+    /// The player who revealed a mine.
+    #[serde(default)]
+    loser: Option<UserId>,
 }
 
 impl MinesweeperGame {
-    fn new(player: UserId, mines: usize) -> MinesweeperGame {
+    pub(crate) fn new(player: UserId, mines: usize) -> MinesweeperGame {
         MinesweeperGame {
             player,
-            mines,
             start_time: None,
-            board: None,
+            board: MinesweeperBoard::new(MAX_WIDTH, MAX_HEIGHT, mines),
+            history: Vec::new(),
+            token: None,
+            locale: Locale::default(),
+            coop: false,
+            invited: None,
+            helpers: Vec::new(),
+            race: false,
+            racers: HashMap::new(),
+            winner: None,
+            lobby: false,
+            cleared: HashMap::new(),
+            loser: None,
         }
     }
 
-    fn start_game(&mut self, safe_cell_index: usize) {
-        let rng = &mut rand::thread_rng();
-        let mut board = repeat_with(|| MinesweeperCell::Safe)
-            .take(25)
-            .collect::<Vec<_>>();
-        let bombs = (0..safe_cell_index)
-            .chain((safe_cell_index + 1)..25)
-            .collect::<Vec<_>>();
-        let bombs = bombs.choose_multiple(rng, self.mines).cloned();
-        for b in bombs {
-            board[b] = MinesweeperCell::Bomb;
+    /// The player who made the game, then everyone who joined.
+    fn players(&self) -> impl Iterator<Item = UserId> + '_ {
+        iter::once(self.player).chain(self.helpers.iter().copied())
+    }
+
+    fn player_list(&self) -> String {
+        self.players()
+            .map(|player| player.mention().to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Who cleared how many tiles, and who hit a mine, once a co-op game is
+    /// over.
+    fn credits(&self) -> String {
+        let credits = self
+            .players()
+            .map(|player| {
+                let cleared = self.cleared.get(&player).copied().unwrap_or_default();
+                format!("{} {}", player.mention(), cleared)
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        let mut content = tr!(self.locale, "minesweeper-credits", credits = credits);
+        if let Some(loser) = self.loser {
+            let hit = tr!(
+                self.locale,
+                "minesweeper-hit-mine",
+                player = loser.mention().to_string()
+            );
+            content = format!("{}\n{}", content, hit);
         }
-        self.start_time = Some(Instant::now());
-        self.board = Some(board);
+        content
+    }
+
+    /// Whether someone cleared their board, or everyone hit a mine.
+    fn race_over(&self) -> bool {
+        self.winner.is_some()
+            || (!self.racers.is_empty() && self.racers.values().all(|racer| racer.board.is_over()))
+    }
+
+    /// Time between the first and last tiles being clicked.
+    fn play_time(&self) -> Duration {
+        match (self.start_time, self.history.last()) {
+            (Some(start), Some(last)) => last.time.duration_since(start).unwrap_or_default(),
+            _ => Duration::ZERO,
+        }
+    }
+
+    /// Names the board in players' records. Boards with an opening are
+    /// easier, so they are kept apart.
+    fn record_name(&self) -> String {
+        let board = &self.board;
+        let opening = if board.opening() { "-opening" } else { "" };
+        format!(
+            "minesweeper-{}x{}-{}{}",
+            board.width(),
+            board.height(),
+            board.mines(),
+            opening
+        )
+    }
+
+    /// Gives every player the same board and starts the clock at `time`. The
+    /// mines are laid out around the middle tile, which is cleared for
+    /// everyone so that nobody's first click can hit a mine.
+    fn start_race(&mut self, time: SystemTime) {
+        let board = &self.board;
+        let middle = board.height() / 2 * board.width() + board.width() / 2;
+        let mut dealt = board.replay();
+        dealt.place_mines(middle);
+        // Unless that would clear the whole board, leaving nothing to race on
+        let mut opened = dealt.clone();
+        if let Ok(MinesweeperOutcome::Cleared(_)) =
+            apply_move(&mut opened, MinesweeperAction::Reveal(middle))
+        {
+            dealt = opened;
+        }
+
+        let racers = self
+            .players()
+            .map(|player| {
+                let racer = Racer {
+                    board: dealt.clone(),
+                    time: None,
+                };
+                (player, racer)
+            })
+            .collect();
+        self.racers = racers;
+        self.start_time = Some(time);
+    }
+
+    /// The numbers asked for by the lobby's "Custom game" form.
+    fn setup_fields(&self) -> [Field; 3] {
+        [
+            Field {
+                id: "width",
+                min: 3,
+                max: MAX_WIDTH,
+                value: self.board.width(),
+            },
+            Field {
+                id: "height",
+                min: 3,
+                max: MAX_HEIGHT,
+                value: self.board.height(),
+            },
+            Field {
+                id: "mines",
+                min: 1,
+                max: MinesweeperBoard::max_mines(MAX_WIDTH, MAX_HEIGHT, true),
+                value: self.board.mines(),
+            },
+        ]
+    }
+
+    /// The preset the board matches, if any.
+    fn preset(&self) -> Option<Preset> {
+        let board = (self.board.width(), self.board.height(), self.board.mines());
+        Preset::ALL
+            .into_iter()
+            .find(|preset| preset.board() == board)
+    }
+
+    /// The name of the board's preset, or its size if it was made to order.
+    fn level(&self) -> String {
+        match self.preset() {
+            Some(preset) => tr!(self.locale, &format!("minesweeper-{}", preset.name())),
+            None => tr!(
+                self.locale,
+                "minesweeper-custom",
+                width = self.board.width(),
+                height = self.board.height()
+            ),
+        }
+    }
+
+    /// The board after the first `step` moves, dealt again from its seed, or
+    /// `None` if the moves don't play out again.
+    fn board_at(&self, step: usize) -> Option<MinesweeperBoard> {
+        let mut replay = self.board.replay();
+        for record in self.history.get(..step)? {
+            apply_move(&mut replay, record.action).ok()?;
+        }
+        Some(replay)
+    }
+
+    /// The game in PGN-like notation.
+    fn export(&self) -> String {
+        let start = self.start_time.unwrap();
+        let moves = timed_moves(start, &self.history, |action| match action {
+            MinesweeperAction::Reveal(index) => cell_name(*index, self.board.width()),
+        });
+        let result = match (self.board.is_over(), self.board.safe_remaining()) {
+            (false, _) => "*",
+            (true, 0) => "won",
+            (true, _) => "lost",
+        };
+
+        let tags = [
+            ("Game", String::from("Minesweeper")),
+            (
+                "Size",
+                format!("{}x{}", self.board.width(), self.board.height()),
+            ),
+            ("Mines", self.board.mines().to_string()),
+            ("Seed", self.board.seed().to_string()),
+            ("Opening", self.board.opening().to_string()),
+            ("Start", unix_time(start).to_string()),
+            ("Player", self.player.to_string()),
+            ("Result", String::from(result)),
+        ];
+        let mut tags = tags.to_vec();
+        if self.coop {
+            let helpers = self.helpers.iter().map(|helper| helper.to_string());
+            tags.insert(7, ("Helpers", helpers.collect::<Vec<_>>().join(",")));
+        }
+        export(&tags, &moves, result)
     }
 }
 
-pub struct MinesweeperGames;
+impl Game for MinesweeperGame {
+    const NAME: &'static str = "minesweeper";
 
-impl TypeMapKey for MinesweeperGames {
-    type Value = HashMap<String, MinesweeperGame>;
+    fn is_over(&self) -> bool {
+        if self.race {
+            self.race_over()
+        } else {
+            self.board.is_over()
+        }
+    }
+
+    fn has_player(&self, user: UserId) -> bool {
+        self.player == user || self.helpers.contains(&user)
+    }
+
+    fn token(&self) -> Option<&str> {
+        self.token.as_deref()
+    }
+
+    fn locale(&self) -> Locale {
+        self.locale
+    }
 }
 
+pub type MinesweeperGames = GameStore<MinesweeperGame>;
+
 fn render_board(
-    game: &MinesweeperGame,
-    id: String,
+    board: &MinesweeperBoard,
+    id: GameId,
     selected_cells: &Option<Vec<usize>>,
-    game_over: bool,
-) -> CreateComponents {
-    let mut components = CreateComponents::default();
-
-    if let Some(board) = &game.board {
-        for y in 0..5 {
-            components.create_action_row(|row| {
-                for x in 0..5 {
-                    row.create_button(|button| {
-                        let index = y * 5 + x;
-                        let cell = &board[index];
-                        let (emoji, cell_style, disabled) = match cell {
-                            MinesweeperCell::Bomb => (
-                                if game_over {
-                                    String::from("\u{1F4A3}")
-                                } else {
-                                    String::from("\u{1F7E6}")
-                                },
-                                ButtonStyle::Danger,
-                                game_over,
-                            ),
-                            MinesweeperCell::Safe => (
-                                if game_over {
-                                    number_to_emoji(count_adjacent_bombs(board, index))
-                                } else {
-                                    String::from("\u{1F7E6}")
-                                },
-                                ButtonStyle::Secondary,
-                                game_over,
-                            ),
-                            MinesweeperCell::Checked => (
-                                number_to_emoji(count_adjacent_bombs(board, index)),
-                                ButtonStyle::Success,
-                                true,
-                            ),
-                        };
-
-                        let mut style = ButtonStyle::Secondary;
-
-                        if game_over && cell_style == ButtonStyle::Success {
+) -> Vec<CreateActionRow> {
+    let game_over = board.is_over();
+
+    (0..board.height())
+        .map(|y| {
+            let buttons = (0..board.width())
+                .map(|x| {
+                    let index = y * board.width() + x;
+                    let button = CreateButton::new(format!("minesweeper-{}-{}", id, index));
+                    let cells = match board.cells() {
+                        Some(cells) => cells,
+                        None => {
+                            return button
+                                .style(ButtonStyle::Secondary)
+                                .emoji(ReactionType::Unicode(String::from("\u{1F7E6}")))
+                        }
+                    };
+
+                    let (emoji, cell_style, disabled) = match cells[index] {
+                        MinesweeperCell::Bomb => (
+                            if game_over {
+                                String::from("\u{1F4A3}")
+                            } else {
+                                String::from("\u{1F7E6}")
+                            },
+                            ButtonStyle::Danger,
+                            game_over,
+                        ),
+                        MinesweeperCell::Safe => (
+                            if game_over {
+                                number_to_emoji(board.adjacent_bombs(index))
+                            } else {
+                                String::from("\u{1F7E6}")
+                            },
+                            ButtonStyle::Secondary,
+                            game_over,
+                        ),
+                        MinesweeperCell::Checked => (
+                            number_to_emoji(board.adjacent_bombs(index)),
+                            ButtonStyle::Success,
+                            true,
+                        ),
+                    };
+
+                    let mut style = ButtonStyle::Secondary;
+
+                    if game_over && cell_style == ButtonStyle::Success {
+                        style = cell_style;
+                    }
+
+                    if let Some(cells) = selected_cells {
+                        if cells.contains(&index) {
                             style = cell_style;
                         }
+                    };
 
-                        if let Some(cells) = selected_cells {
-                            if cells.iter().any(|&c| c == index) {
-                                style = cell_style;
-                            }
-                        };
-
-                        button
-                            .custom_id(format!("minesweeper-{}-{}", id, index))
-                            .style(style)
-                            .emoji(ReactionType::Unicode(emoji))
-                            .disabled(disabled)
-                    });
-                }
-                row
-            });
-        }
+                    button
+                        .style(style)
+                        .emoji(ReactionType::Unicode(emoji))
+                        .disabled(disabled)
+                })
+                .collect();
+            CreateActionRow::Buttons(buttons)
+        })
+        .collect()
+}
+
+/// The message asking others to join a co-op game, before the board is shown.
+fn render_lobby(game: &MinesweeperGame, id: GameId) -> (String, Vec<CreateActionRow>) {
+    let locale = game.locale;
+    let player = game.player.mention().to_string();
+    let mode = if game.race { "race" } else { "coop" };
+    let invitation = match game.invited {
+        Some(invited) => tr!(
+            locale,
+            &format!("minesweeper-{}-invited", mode),
+            player = player,
+            invited = invited.mention().to_string()
+        ),
+        None => tr!(
+            locale,
+            &format!("minesweeper-{}-started", mode),
+            player = player
+        ),
+    };
+    let content = format!(
+        "{}\n{}\n{}",
+        invitation,
+        tr!(locale, "minesweeper-players", players = game.player_list()),
+        tr!(locale, "minesweeper-level", level = game.level())
+    );
+    let buttons = vec![
+        CreateButton::new(format!("minesweeper-{}-join", id))
+            .label(tr!(locale, "minesweeper-join"))
+            .style(ButtonStyle::Success),
+        CreateButton::new(format!("minesweeper-{}-start", id))
+            .label(tr!(locale, "minesweeper-start"))
+            .style(ButtonStyle::Primary),
+        custom_button(format!("minesweeper-{}-custom", id), locale),
+    ];
+    (content, vec![CreateActionRow::Buttons(buttons)])
+}
+
+/// The race's message in the channel: how far everyone got, and who won once
+/// someone clears their board.
+fn render_scoreboard(game: &MinesweeperGame, id: GameId) -> (String, Vec<CreateActionRow>) {
+    let locale = game.locale;
+    let over = game.race_over();
+    let heading = match game.winner {
+        Some(winner) => tr!(
+            locale,
+            "minesweeper-race-won",
+            player = winner.mention().to_string()
+        ),
+        None if over => tr!(locale, "minesweeper-race-no-winner"),
+        None => tr!(locale, "minesweeper-race-playing"),
+    };
+    let lines = game.players().filter_map(|player| {
+        let racer = game.racers.get(&player)?;
+        let (cleared, safe) = racer.progress();
+        let player = player.mention().to_string();
+        Some(match racer.time {
+            Some(time) if cleared == safe => tr!(
+                locale,
+                "minesweeper-race-cleared",
+                player = player,
+                seconds = format_seconds(time)
+            ),
+            Some(time) => tr!(
+                locale,
+                "minesweeper-race-hit-mine",
+                player = player,
+                cleared = cleared,
+                safe = safe,
+                seconds = format_seconds(time)
+            ),
+            None => tr!(
+                locale,
+                "minesweeper-race-progress",
+                player = player,
+                cleared = cleared,
+                safe = safe
+            ),
+        })
+    });
+    let content = iter::once(heading)
+        .chain(lines)
+        .chain(iter::once(tr!(
+            locale,
+            "minesweeper-level",
+            level = game.level()
+        )))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let components = if over {
+        Vec::new()
     } else {
-        for y in 0..5 {
-            components.create_action_row(|row| {
-                for x in 0..5 {
-                    row.create_button(|button| {
-                        let index = y * 5 + x;
-                        button
-                            .custom_id(format!("minesweeper-{}-{}", id, index))
-                            .style(ButtonStyle::Secondary)
-                            .emoji(ReactionType::Unicode(String::from("\u{1F7E6}")))
-                    });
-                }
-                row
-            });
-        }
+        vec![CreateActionRow::Buttons(vec![CreateButton::new(format!(
+            "minesweeper-{}-play",
+            id
+        ))
+        .label(tr!(locale, "minesweeper-race-play"))
+        .style(ButtonStyle::Primary)])]
+    };
+    (content, components)
+}
+
+/// A `width` by `height` board with `mines`, or why there can't be one.
+/// Larger boards get an opening unless `opening` says otherwise.
+fn deal(
+    width: usize,
+    height: usize,
+    mines: usize,
+    opening: Option<bool>,
+    locale: Locale,
+) -> Result<MinesweeperBoard, String> {
+    let opening = opening.unwrap_or(width * height >= OPENING_MIN_TILES);
+    let max = MinesweeperBoard::max_mines(width, height, opening);
+    if mines > max {
+        return Err(tr!(
+            locale,
+            "minesweeper-too-many-mines",
+            width = width,
+            height = height,
+            max = max,
+            safe = if opening { "opening" } else { "tile" }
+        ));
     }
+    Ok(MinesweeperBoard::new(width, height, mines).with_opening(opening))
+}
 
-    components
+/// The board as emoji, for replays.
+fn render_text_board(board: &MinesweeperBoard) -> String {
+    (0..board.height())
+        .map(|y| {
+            (0..board.width())
+                .map(|x| {
+                    let index = y * board.width() + x;
+                    match board.cells().map(|cells| cells[index]) {
+                        Some(MinesweeperCell::Checked) => {
+                            number_to_emoji(board.adjacent_bombs(index))
+                        }
+                        Some(MinesweeperCell::Bomb) if board.is_over() => String::from("\u{1F4A3}"),
+                        _ => String::from("\u{1F7E6}"),
+                    }
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
 }
 
 fn number_to_emoji(number: usize) -> String {
@@ -171,220 +597,1212 @@ fn number_to_emoji(number: usize) -> String {
     String::from(str)
 }
 
-fn get_adjacent_indexes(index: usize) -> Vec<usize> {
-    let mut cells = Vec::new();
-
-    let iy = index / 5;
-    let ix = index % 5;
-
-    for y in iy as isize - 1..iy as isize + 2 {
-        if !(0..5_isize).contains(&y) {
-            continue;
-        }
-        for x in ix as isize - 1..ix as isize + 2 {
-            if !(0..5_isize).contains(&x) {
-                continue;
-            }
-            if x == ix as isize && y == iy as isize {
-                continue;
-            }
-
-            cells.push(y as usize * 5 + x as usize);
+pub async fn minesweeper(
+    data: &RwLock<TypeMap>,
+    responder: &impl Responder,
+    command: &CommandInteraction,
+) -> Result<(), Error> {
+    // Width, height and mines given on their own change the preset's board
+    let options = command.data.options();
+    let preset = options
+        .iter()
+        .find_map(|option| match option.value {
+            ResolvedValue::String(name) => Preset::ALL.into_iter().find(|p| p.name() == name),
+            _ => None,
+        })
+        .unwrap_or(Preset::Beginner);
+    let (mut width, mut height, mut mines) = preset.board();
+    let mut opening = None;
+    let mut coop = false;
+    let mut race = false;
+    let mut invited = None;
+    for option in &options {
+        match (option.name, &option.value) {
+            ("width", ResolvedValue::Integer(n)) => width = (*n as usize).clamp(1, MAX_WIDTH),
+            ("height", ResolvedValue::Integer(n)) => height = (*n as usize).clamp(1, MAX_HEIGHT),
+            ("mines", ResolvedValue::Integer(n)) => mines = *n as usize,
+            ("opening", ResolvedValue::Boolean(on)) => opening = Some(*on),
+            ("coop", ResolvedValue::Boolean(on)) => coop = *on,
+            ("invite", ResolvedValue::User(user, _)) => invited = Some(*user),
+            ("race", ResolvedValue::Boolean(on)) => race = *on,
+            _ => {}
         }
     }
 
-    cells
-}
-
-fn count_adjacent_bombs(board: &[MinesweeperCell], index: usize) -> usize {
-    get_adjacent_indexes(index)
-        .iter()
-        .filter(|&&c| matches!(board[c], MinesweeperCell::Bomb))
-        .count()
-}
-
-fn zero_fill(board: &Vec<MinesweeperCell>, mut set: HashSet<usize>) -> HashSet<usize> {
-    let prev = set.clone();
-    for s in prev.iter() {
-        if count_adjacent_bombs(board, *s) == 0 {
-            set.extend(get_adjacent_indexes(*s).iter());
-            set = set
-                .into_iter()
-                .filter(|&c| !matches!(board[c], MinesweeperCell::Checked))
-                .collect();
+    let user_locale = Locale::of_user(&command.locale);
+    let problem = match invited {
+        Some(invited) if invited.id == command.user.id => {
+            Some(tr!(user_locale, "minesweeper-invite-self"))
         }
-    }
-    if prev == set {
-        return set;
-    }
+        Some(invited) if invited.bot => Some(tr!(user_locale, "minesweeper-invite-bot")),
+        _ if coop && race => Some(tr!(user_locale, "minesweeper-coop-or-race")),
+        _ => None,
+    };
+    let board = match problem.map_or_else(|| deal(width, height, mines, opening, user_locale), Err)
+    {
+        Ok(board) => board,
+        Err(problem) => {
+            return responder
+                .create_interaction_response(CreateInteractionResponse::Message(
+                    CreateInteractionResponseMessage::new()
+                        .content(problem)
+                        .ephemeral(true),
+                ))
+                .await;
+        }
+    };
 
-    zero_fill(board, set)
-}
+    let mut game = MinesweeperGame::new(command.user.id, mines);
+    game.board = board;
+    game.token = Some(command.token.clone());
+    game.locale = Locale::of_guild(command.guild_locale.as_deref(), &command.locale);
+    game.invited = invited.map(|invited| invited.id);
+    game.race = race;
+    game.coop = !race && (coop || game.invited.is_some());
+    game.lobby = game.coop || game.race;
+    let game_id = GameId::from(command.id);
+    game_id.record();
 
-pub async fn minesweeper(
-    ctx: &Context,
-    command: &ApplicationCommandInteraction,
-) -> Result<(), Error> {
-    let mut bombs = 3;
-    if let Some(option) = command.data.options.get(0) {
-        if let Some(ApplicationCommandInteractionDataOptionValue::Integer(count)) = &option.resolved
-        {
-            bombs = *count as usize;
-        }
-    }
-    let mut game_data = ctx.data.write().await;
-    let game_list = game_data.get_mut::<MinesweeperGames>().unwrap();
-    game_list.insert(
-        command.id.to_string(),
-        MinesweeperGame::new(command.user.id, bombs),
-    );
+    let message = if game.lobby {
+        let (content, components) = render_lobby(&game, game_id);
+        // Only an invited player is pinged
+        let mentions = match game.invited {
+            Some(invited) => CreateAllowedMentions::new().users([invited]),
+            None => CreateAllowedMentions::new().empty_users(),
+        };
+        CreateInteractionResponseMessage::new()
+            .content(content)
+            .allowed_mentions(mentions)
+            .components(components)
+    } else {
+        CreateInteractionResponseMessage::new().components(render_board(
+            &game.board,
+            game_id,
+            &None,
+        ))
+    };
+    MinesweeperGames::of(data).await.insert(game_id, game);
 
-    command
-        .create_interaction_response(&ctx.http, |response| {
-            response
-                .kind(InteractionResponseType::ChannelMessageWithSource)
-                .interaction_response_data(|response_data| {
-                    response_data.set_components(render_board(
-                        game_list.get(&command.id.to_string()).unwrap(),
-                        command.id.to_string(),
-                        &None,
-                        false,
-                    ))
-                })
-        })
+    responder
+        .create_interaction_response(CreateInteractionResponse::Message(message))
         .await?;
     Ok(())
 }
 
+/// The answer to a button press, worked out while the game is locked.
+enum Reply {
+    /// A message only the player who pressed the button can see.
+    Private(String),
+    /// A new version of the message the button is on. Finished games also
+    /// get a Replay button.
+    Update {
+        content: Option<String>,
+        components: Vec<CreateActionRow>,
+        over: bool,
+    },
+    /// A replay only the player who pressed the button can see, with the
+    /// game attached.
+    Replay(String, CreateActionRow, CreateAttachment),
+    /// A form for the player who pressed the button to fill in.
+    Modal(CreateModal),
+    /// A racer's own board, which only they can see.
+    Board(String, Vec<CreateActionRow>),
+    /// A new version of a racer's own board, and of the race's message in
+    /// the channel, sent to the interaction `token`.
+    Race {
+        board: Vec<CreateActionRow>,
+        token: Option<String>,
+        content: String,
+        scoreboard: Vec<CreateActionRow>,
+    },
+}
+
 pub async fn minesweeper_button(
-    ctx: &Context,
-    component: &MessageComponentInteraction,
+    data: &RwLock<TypeMap>,
+    responder: &impl Responder,
+    component: &ComponentInteraction,
 ) -> Result<(), Error> {
     let component_id = component.data.custom_id.clone();
     let mut split = component_id.split('-');
     split.next();
-    let game_id = split
+    let game_id: GameId = split
         .next()
-        .ok_or("Missing game id in component custom id")?;
-    let index = split
+        .ok_or("Missing game id in component custom id")?
+        .parse()?;
+    game_id.record();
+    let action = split
         .next()
-        .ok_or("Missing cell index in component custom id")?
-        .parse::<usize>()?;
-
-    let mut game_data = ctx.data.write().await;
-    let game_list = game_data.get_mut::<MinesweeperGames>().unwrap();
-    match game_list.get_mut(game_id) {
-        Some(game) => {
-            if component.user.id == game.player {
-                if game.board.is_none() {
-                    game.start_game(index);
-                }
-
-                let mut game_over = false;
-                let mut selected_cells = vec![index];
-
-                {
-                    let board = &mut game.board.as_mut().unwrap();
-
-                    let selected_cell = &board[index];
-
-                    match selected_cell {
-                        MinesweeperCell::Bomb => game_over = true,
-                        MinesweeperCell::Safe => {
-                            let mut set = HashSet::new();
-                            set.insert(index);
-                            let fill = zero_fill(board, set);
-                            for f in &fill {
-                                board[*f] = MinesweeperCell::Checked;
-                            }
-                            selected_cells = fill.into_iter().collect::<Vec<_>>();
-
-                            if !board.iter().any(|c| matches!(c, MinesweeperCell::Safe)) {
-                                game_over = true;
-                            }
-                        }
-                        MinesweeperCell::Checked => {
-                            return Err(Error::from(
-                                "Checked cell selected which should be disabled.",
-                            ));
-                        }
-                    }
-                }
-
-                component
-                    .create_interaction_response(&ctx.http, |response| {
-                        response
-                            .kind(InteractionResponseType::UpdateMessage)
-                            .interaction_response_data(|data| {
-                                if game_over {
-                                    let safes = game
-                                        .board
-                                        .as_ref()
-                                        .unwrap()
-                                        .iter()
-                                        .filter(|&c| matches!(c, MinesweeperCell::Safe))
-                                        .count();
-                                    let bombs = game.mines;
-
-                                    if safes == 0 {
-                                        data.content(format!(
-                                            "**You win!**\nMines: {}\nTime: {}s",
-                                            bombs,
-                                            game.start_time.unwrap().elapsed().as_secs()
-                                        ));
-                                    } else {
-                                        data.content(format!(
-                                            "**Game over.**\nMines: {}\nCleared: {}/{}\nTime: {}s",
-                                            bombs,
-                                            25 - safes - bombs,
-                                            25 - bombs,
-                                            game.start_time.unwrap().elapsed().as_secs()
-                                        ));
-                                    }
-                                }
-
-                                data.set_components(render_board(
-                                    game,
-                                    String::from(game_id),
-                                    &Some(selected_cells),
-                                    game_over,
-                                ))
-                            })
-                    })
+        .ok_or("Missing cell index in component custom id")?;
+
+    let locale = Locale::of_guild(component.guild_locale.as_deref(), &component.locale);
+    let records = data.read().await.get::<RecordsContainer>().cloned();
+    let reply = MinesweeperGames::of(data).await.with(game_id, |game| {
+        answer_button(
+            game,
+            game_id,
+            action,
+            split.next(),
+            component,
+            records.as_deref(),
+        )
+    });
+    send_reply(responder, game_id, locale, reply.transpose()?).await
+}
+
+/// Applies the co-op lobby's "Custom game" form to the game it was opened
+/// from.
+pub async fn minesweeper_setup(
+    data: &RwLock<TypeMap>,
+    responder: &impl Responder,
+    modal: &ModalInteraction,
+) -> Result<(), Error> {
+    let game_id: GameId = modal
+        .data
+        .custom_id
+        .strip_prefix("minesweeper-setup-")
+        .ok_or("Missing game id in modal custom id")?
+        .parse()?;
+    game_id.record();
+
+    let user_locale = Locale::of_user(&modal.locale);
+    let reply = MinesweeperGames::of(data).await.with(game_id, |game| {
+        if modal.user.id != game.player {
+            return Reply::Private(tr!(user_locale, "minesweeper-host-only"));
+        }
+        if !game.lobby {
+            return Reply::Private(tr!(user_locale, "minesweeper-already-started"));
+        }
+        let board = read(modal, &game.setup_fields(), user_locale)
+            .and_then(|[width, height, mines]| deal(width, height, mines, None, user_locale));
+        match board {
+            Ok(board) => game.board = board,
+            Err(problem) => return Reply::Private(problem),
+        }
+
+        let (content, components) = render_lobby(game, game_id);
+        Reply::Update {
+            content: Some(content),
+            components,
+            over: false,
+        }
+    });
+    let locale = Locale::of_guild(modal.guild_locale.as_deref(), &modal.locale);
+    send_reply(responder, game_id, locale, reply).await
+}
+
+/// Sends the answer to a button press or form, or clears the message of a
+/// game that is no longer stored.
+async fn send_reply(
+    responder: &impl Responder,
+    game_id: GameId,
+    locale: Locale,
+    reply: Option<Reply>,
+) -> Result<(), Error> {
+    match reply {
+        Some(Reply::Replay(content, steps, file)) => {
+            responder
+                .create_interaction_response_with_files(
+                    CreateInteractionResponse::Message(
+                        CreateInteractionResponseMessage::new()
+                            .content(content)
+                            .components(vec![steps])
+                            .ephemeral(true),
+                    ),
+                    vec![file],
+                )
+                .await?;
+        }
+        Some(Reply::Update {
+            content,
+            components,
+            over,
+        }) => {
+            let mut message = CreateInteractionResponseMessage::new()
+                .allowed_mentions(CreateAllowedMentions::new().empty_users())
+                .components(components);
+            if let Some(content) = content {
+                message = message.content(content);
+            }
+            responder
+                .create_interaction_response(CreateInteractionResponse::UpdateMessage(message))
+                .await?;
+
+            // The board fills the message, so the replay gets its own
+            if over {
+                responder
+                    .create_followup_message(
+                        CreateInteractionResponseFollowup::new()
+                            .content(tr!(locale, "replay-prompt"))
+                            .components(vec![CreateActionRow::Buttons(vec![CreateButton::new(
+                                format!("minesweeper-{}-replay", game_id),
+                            )
+                            .label(tr!(locale, "replay"))
+                            .style(ButtonStyle::Secondary)])]),
+                    )
                     .await?;
-                if game_over {
-                    game_list.remove(game_id);
-                }
-            } else {
-                component
-                    .create_interaction_response(&ctx.http, |response| {
-                        response
-                            .kind(InteractionResponseType::ChannelMessageWithSource)
-                            .interaction_response_data(|data| {
-                                data.content(
-                                    "Thats not your game! Create your own with `/minesweeper`.",
-                                )
-                                .ephemeral(true)
-                            })
-                    })
+            }
+        }
+        Some(Reply::Board(content, components)) => {
+            responder
+                .create_interaction_response(CreateInteractionResponse::Message(
+                    CreateInteractionResponseMessage::new()
+                        .content(content)
+                        .components(components)
+                        .ephemeral(true),
+                ))
+                .await?;
+        }
+        Some(Reply::Race {
+            board,
+            token,
+            content,
+            scoreboard,
+        }) => {
+            responder
+                .create_interaction_response(CreateInteractionResponse::UpdateMessage(
+                    CreateInteractionResponseMessage::new().components(board),
+                ))
+                .await?;
+            if let Some(token) = token {
+                responder
+                    .edit_interaction_response(
+                        &token,
+                        EditInteractionResponse::new()
+                            .content(content)
+                            .allowed_mentions(CreateAllowedMentions::new().empty_users())
+                            .components(scoreboard),
+                    )
                     .await?;
             }
         }
+        Some(Reply::Modal(modal)) => {
+            responder
+                .create_interaction_response(CreateInteractionResponse::Modal(modal))
+                .await?;
+        }
+        Some(Reply::Private(content)) => {
+            responder
+                .create_interaction_response(CreateInteractionResponse::Message(
+                    CreateInteractionResponseMessage::new()
+                        .content(content)
+                        .ephemeral(true),
+                ))
+                .await?;
+        }
         None => {
-            component
-                .create_interaction_response(&ctx.http, |response| {
-                    response
-                        .kind(InteractionResponseType::UpdateMessage)
-                        .interaction_response_data(|data| {
-                            data.content(
-                                "This game has expired, start a new one with `/minesweeper`.",
-                            )
-                            .set_components(CreateComponents::default())
-                        })
-                })
+            responder
+                .create_interaction_response(CreateInteractionResponse::UpdateMessage(
+                    CreateInteractionResponseMessage::new()
+                        .content(tr!(locale, "minesweeper-expired"))
+                        .components(Vec::new()),
+                ))
                 .await?;
         }
     };
 
     Ok(())
 }
+
+/// Works out the answer to pressing `action` on `game`. `argument` is the
+/// rest of the button's custom id, if any. Wins are compared with the
+/// player's best in `records`, if they are kept.
+fn answer_button(
+    game: &mut MinesweeperGame,
+    game_id: GameId,
+    action: &str,
+    argument: Option<&str>,
+    component: &ComponentInteraction,
+    records: Option<&Records>,
+) -> Result<Reply, Error> {
+    let user_locale = Locale::of_user(&component.locale);
+    if action == "replay" || action == "step" {
+        // Custom ids can be stale or made up, and races keep no history
+        if game.start_time.is_none() || game.history.is_empty() {
+            return Ok(Reply::Private(tr!(user_locale, "replay-unavailable")));
+        }
+        let step = match action {
+            "step" => argument
+                .ok_or("Missing step in component custom id")?
+                .parse::<usize>()?
+                .min(game.history.len()),
+            _ => 0,
+        };
+
+        let mut content = step_heading(step, game.history.len(), user_locale);
+        if let Some(record) = step.checked_sub(1).map(|i| &game.history[i]) {
+            let MinesweeperAction::Reveal(index) = record.action;
+            content = format!("{}: {}", content, cell_name(index, game.board.width()));
+        }
+        let board = match game.board_at(step) {
+            Some(board) => board,
+            None => return Ok(Reply::Private(tr!(user_locale, "replay-unavailable"))),
+        };
+        content = format!("{}\n\n{}", content, render_text_board(&board));
+        let steps = render_steps(
+            &format!("minesweeper-{}-step", game_id),
+            step,
+            game.history.len(),
+            user_locale,
+        );
+
+        return Ok(if action == "replay" {
+            let file = export_file(format!("minesweeper-{}.txt", game_id), game.export());
+            Reply::Replay(content, steps, file)
+        } else {
+            Reply::Update {
+                content: Some(content),
+                components: vec![steps],
+                over: false,
+            }
+        });
+    }
+
+    let user = component.user.id;
+    match action {
+        "join" | "start" | "custom" if !game.lobby => {
+            return Ok(Reply::Private(tr!(
+                user_locale,
+                "minesweeper-already-started"
+            )));
+        }
+        "join" => {
+            if game.has_player(user) {
+                return Ok(Reply::Private(tr!(
+                    user_locale,
+                    "minesweeper-already-joined"
+                )));
+            }
+            if game.invited.is_some_and(|invited| invited != user) {
+                return Ok(Reply::Private(tr!(user_locale, "minesweeper-not-invited")));
+            }
+
+            game.helpers.push(user);
+            let (content, components) = render_lobby(game, game_id);
+            return Ok(Reply::Update {
+                content: Some(content),
+                components,
+                over: false,
+            });
+        }
+        "start" | "custom" if user != game.player => {
+            return Ok(Reply::Private(tr!(user_locale, "minesweeper-host-only")));
+        }
+        "start" if game.race && game.helpers.is_empty() => {
+            return Ok(Reply::Private(tr!(user_locale, "minesweeper-race-alone")));
+        }
+        "start" if game.race => {
+            game.lobby = false;
+            game.token = Some(component.token.clone());
+            game.start_race(click_time(component.id));
+            let (content, components) = render_scoreboard(game, game_id);
+            return Ok(Reply::Update {
+                content: Some(content),
+                components,
+                over: false,
+            });
+        }
+        "start" => {
+            game.lobby = false;
+            game.token = Some(component.token.clone());
+            let players = game.player_list();
+            return Ok(Reply::Update {
+                content: Some(tr!(
+                    game.locale,
+                    "minesweeper-coop-playing",
+                    players = players
+                )),
+                components: render_board(&game.board, game_id, &None),
+                over: false,
+            });
+        }
+        "custom" => {
+            let custom_id = format!("minesweeper-setup-{}", game_id);
+            return Ok(Reply::Modal(modal(
+                custom_id,
+                &game.setup_fields(),
+                user_locale,
+            )));
+        }
+        _ => {}
+    }
+
+    if !game.has_player(user) {
+        return Ok(Reply::Private(tr!(user_locale, "minesweeper-not-yours")));
+    }
+    if game.lobby {
+        return Ok(Reply::Private(tr!(user_locale, "minesweeper-not-started")));
+    }
+    if game.race {
+        return answer_race(game, game_id, action, component, user_locale);
+    }
+    let index = action.parse::<usize>()?;
+
+    let clicked = click_time(component.id);
+    if game.start_time.is_none() {
+        game.start_time = Some(clicked);
+    }
+
+    let outcome = match apply_move(&mut game.board, MinesweeperAction::Reveal(index)) {
+        Ok(outcome) => outcome,
+        Err(err) => return refuse_move(err, user_locale),
+    };
+    let selected_cells = match outcome {
+        MinesweeperOutcome::Cleared(cells) | MinesweeperOutcome::Won(cells) => {
+            *game.cleared.entry(user).or_default() += cells.len();
+            cells
+        }
+        MinesweeperOutcome::Lost(cell) => {
+            game.loser = Some(user);
+            vec![cell]
+        }
+    };
+    game.history
+        .push(MoveRecord::at(MinesweeperAction::Reveal(index), clicked));
+    game.token = Some(component.token.clone());
+
+    let board = &game.board;
+    let mut content = if board.is_over() {
+        let safes = board.safe_remaining();
+        let bombs = board.mines();
+        let size = board.width() * board.height();
+        let time = format_seconds(game.play_time());
+
+        if safes == 0 {
+            Some(tr!(
+                game.locale,
+                "minesweeper-won",
+                mines = bombs,
+                seconds = time,
+                level = game.level()
+            ))
+        } else {
+            Some(tr!(
+                game.locale,
+                "minesweeper-lost",
+                mines = bombs,
+                cleared = size - safes - bombs,
+                safe = size - bombs,
+                seconds = time,
+                level = game.level()
+            ))
+        }
+    } else {
+        None
+    };
+    if let Some(result) = content.as_mut().filter(|_| game.coop) {
+        *result = format!("{}\n{}", result, game.credits());
+    }
+    // Co-op wins are shared, so only games played alone count as records.
+    // A win in one click takes no time at all, which nobody could beat.
+    let won = board.is_over() && board.safe_remaining() == 0;
+    let timed = game.history.len() > 1;
+    if let Some(records) = records.filter(|_| won && timed && !game.coop) {
+        let best = match records.submit(game.player, &game.record_name(), game.play_time()) {
+            Best::First => None,
+            Best::Improved(by) => Some(tr!(
+                game.locale,
+                "minesweeper-best-improved",
+                seconds = format_seconds(by)
+            )),
+            Best::Kept(best) => Some(tr!(
+                game.locale,
+                "minesweeper-best-kept",
+                seconds = format_seconds(best)
+            )),
+        };
+        if let (Some(result), Some(best)) = (content.as_mut(), best) {
+            *result = format!("{}\n{}", result, best);
+        }
+    }
+
+    Ok(Reply::Update {
+        content,
+        components: render_board(board, game_id, &Some(selected_cells)),
+        over: board.is_over(),
+    })
+}
+
+/// Tells the player why their click did nothing, when someone else got there
+/// first. Anything else is a bug.
+fn refuse_move(err: MoveError, user_locale: Locale) -> Result<Reply, Error> {
+    match err {
+        MoveError::Occupied => Ok(Reply::Private(tr!(
+            user_locale,
+            "minesweeper-already-cleared"
+        ))),
+        MoveError::GameOver => Ok(Reply::Private(tr!(user_locale, "minesweeper-over"))),
+        err => Err(err.into()),
+    }
+}
+
+/// Works out the answer to `user` pressing `action` in a race that has
+/// started: showing them their board, or revealing one of its tiles.
+fn answer_race(
+    game: &mut MinesweeperGame,
+    game_id: GameId,
+    action: &str,
+    component: &ComponentInteraction,
+    user_locale: Locale,
+) -> Result<Reply, Error> {
+    let user = component.user.id;
+    if game.race_over() {
+        return Ok(Reply::Private(tr!(user_locale, "minesweeper-race-over")));
+    }
+    let start = game.start_time.ok_or("Race started without a start time")?;
+    let racer = game
+        .racers
+        .get_mut(&user)
+        .ok_or("Missing board for player in race")?;
+    if action == "play" {
+        return Ok(Reply::Board(
+            tr!(user_locale, "minesweeper-race-board"),
+            render_board(&racer.board, game_id, &None),
+        ));
+    }
+
+    let index = action
+        .parse::<usize>()
+        .map_err(|_| "Invalid cell index in component custom id")?;
+    let outcome = match apply_move(&mut racer.board, MinesweeperAction::Reveal(index)) {
+        Ok(outcome) => outcome,
+        Err(err) => return refuse_move(err, user_locale),
+    };
+    let selected_cells = match outcome {
+        MinesweeperOutcome::Cleared(cells) | MinesweeperOutcome::Won(cells) => cells,
+        MinesweeperOutcome::Lost(cell) => vec![cell],
+    };
+    if racer.board.is_over() {
+        let time = click_time(component.id).duration_since(start);
+        racer.time = Some(time.unwrap_or_default());
+        if racer.board.safe_remaining() == 0 {
+            game.winner = Some(user);
+        }
+    }
+
+    let board = render_board(&game.racers[&user].board, game_id, &Some(selected_cells));
+    let (content, scoreboard) = render_scoreboard(game, game_id);
+    Ok(Reply::Race {
+        board,
+        token: game.token.clone(),
+        content,
+        scoreboard,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use serenity::json::{json, Value};
+    use serenity::model::id::InteractionId;
+
+    use super::*;
+    use crate::commands::responder::testing::*;
+
+    const GAME: GameId = GameId(1);
+    const PLAYER: UserId = UserId::new(10);
+
+    fn games(game: Option<MinesweeperGame>) -> RwLock<TypeMap> {
+        let games = GameStore::default();
+        if let Some(game) = game {
+            games.insert(GAME, game);
+        }
+        let mut data = TypeMap::new();
+        data.insert::<MinesweeperGames>(games);
+        data.insert::<RecordsContainer>(Arc::new(Records::default()));
+        RwLock::new(data)
+    }
+
+    /// A copy of the game as it is now.
+    async fn stored_game(data: &RwLock<TypeMap>) -> MinesweeperGame {
+        MinesweeperGames::of(data)
+            .await
+            .with(GAME, |game| game.clone())
+            .unwrap()
+    }
+
+    /// A started game with mines at `mines`.
+    fn game_with_mines(mines: &[usize]) -> MinesweeperGame {
+        let mut game = MinesweeperGame::new(PLAYER, mines.len());
+        game.start_time = Some(SystemTime::now());
+        game.board = MinesweeperBoard::with_mines(5, 5, mines);
+        game
+    }
+
+    async fn press_with(
+        data: &RwLock<TypeMap>,
+        responder: &RecordingResponder,
+        action: &str,
+        user: UserId,
+    ) -> Value {
+        let custom_id = format!("minesweeper-{}-{}", GAME, action);
+        let component = component_interaction(&custom_id, user, 1, PLAYER);
+        minesweeper_button(data, responder, &component)
+            .await
+            .unwrap();
+        responder.last()
+    }
+
+    /// Clicks the tile `index` at `millis` after Discord's epoch.
+    async fn click_at(data: &RwLock<TypeMap>, index: usize, millis: u64) -> Value {
+        let responder = RecordingResponder::default();
+        let custom_id = format!("minesweeper-{}-{}", GAME, index);
+        let mut component = component_interaction(&custom_id, PLAYER, 1, PLAYER);
+        component.id = InteractionId::new(millis << 22);
+        minesweeper_button(data, &responder, &component)
+            .await
+            .unwrap();
+        responder.last()
+    }
+
+    async fn press(data: &RwLock<TypeMap>, index: usize, user: UserId) -> Value {
+        press_with(
+            data,
+            &RecordingResponder::default(),
+            &index.to_string(),
+            user,
+        )
+        .await
+    }
+
+    async fn start(options: Value) -> (RwLock<TypeMap>, Value) {
+        let data = games(None);
+        let responder = RecordingResponder::default();
+        let command = command_interaction("minesweeper", PLAYER, 1, options, json!({}));
+        minesweeper(&data, &responder, &command).await.unwrap();
+        (data, responder.last())
+    }
+
+    #[tokio::test]
+    async fn presets_can_be_changed() {
+        let (data, _) = start(json!([{"name": "preset", "type": 3, "value": "expert"}])).await;
+        let game = stored_game(&data).await;
+        assert_eq!(game.board.mines(), 10);
+        assert_eq!(game.level(), "Expert");
+        assert!(game.board.opening());
+
+        let (data, response) = start(json!([
+            {"name": "mines", "type": 4, "value": 2},
+            {"name": "width", "type": 4, "value": 4},
+            {"name": "height", "type": 4, "value": 3},
+        ]))
+        .await;
+        assert_eq!(button_ids(&response).len(), 3);
+        assert_eq!(button_ids(&response)[0].len(), 4);
+        let game = stored_game(&data).await;
+        assert_eq!(game.board.mines(), 2);
+        assert_eq!(game.level(), "Custom 4x3");
+        // Too small for an opening unless asked for
+        assert!(!game.board.opening());
+
+        let (data, _) = start(json!([
+            {"name": "preset", "type": 3, "value": "beginner"},
+            {"name": "opening", "type": 5, "value": false},
+        ]))
+        .await;
+        assert!(!stored_game(&data).await.board.opening());
+    }
+
+    #[tokio::test]
+    async fn mines_leave_room_for_an_opening() {
+        let (data, response) = start(json!([
+            {"name": "width", "type": 4, "value": 3},
+            {"name": "height", "type": 4, "value": 4},
+            {"name": "mines", "type": 4, "value": 4},
+            {"name": "opening", "type": 5, "value": true},
+        ]))
+        .await;
+
+        assert_eq!(
+            content(&response),
+            "A 3x4 board has room for at most 3 mines, keeping the first tile you clear and the ones around it safe."
+        );
+        assert_eq!(response["data"]["flags"], 64);
+        assert!(MinesweeperGames::of(&data)
+            .await
+            .with(GAME, |_| ())
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn smallest_boards_have_room_for_mines() {
+        let (data, response) = start(json!([
+            {"name": "width", "type": 4, "value": 3},
+            {"name": "height", "type": 4, "value": 3},
+            {"name": "mines", "type": 4, "value": 8},
+        ]))
+        .await;
+        assert_eq!(response["type"], 4);
+        assert_eq!(button_ids(&response).len(), 3);
+        assert!(press(&data, 4, PLAYER)
+            .await
+            .to_string()
+            .contains("You win!"));
+
+        let (data, _) = start(json!([
+            {"name": "width", "type": 4, "value": 3},
+            {"name": "height", "type": 4, "value": 3},
+            {"name": "mines", "type": 4, "value": 1},
+            {"name": "opening", "type": 5, "value": true},
+        ]))
+        .await;
+        press(&data, 4, PLAYER).await;
+        // The opening gives up a tile for the mine, so the click is safe
+        let game = stored_game(&data).await;
+        assert!(!game.board.is_over());
+        assert_eq!(game.board.safe_remaining(), 7);
+
+        let response = start(json!([
+            {"name": "width", "type": 4, "value": 3},
+            {"name": "height", "type": 4, "value": 3},
+            {"name": "mines", "type": 4, "value": 9},
+        ]))
+        .await
+        .1;
+        assert_eq!(
+            content(&response),
+            "A 3x3 board has room for at most 8 mines, keeping the first tile you clear safe."
+        );
+    }
+
+    #[tokio::test]
+    async fn full_boards_without_an_opening_take_more_mines() {
+        let (data, response) = start(json!([
+            {"name": "mines", "type": 4, "value": 24},
+            {"name": "opening", "type": 5, "value": false},
+        ]))
+        .await;
+        assert_eq!(response["type"], 4);
+        assert_eq!(stored_game(&data).await.board.mines(), 24);
+
+        let response = start(json!([{"name": "mines", "type": 4, "value": 24}]))
+            .await
+            .1;
+        assert_eq!(
+            content(&response),
+            "A 5x5 board has room for at most 16 mines, keeping the first tile you clear and the ones around it safe."
+        );
+    }
+
+    const HELPER: UserId = UserId::new(20);
+
+    /// A co-op game `HELPER` has joined, started with mines at `mines`.
+    async fn coop_game(mines: &[usize]) -> RwLock<TypeMap> {
+        let (data, _) = start(json!([{"name": "coop", "type": 5, "value": true}])).await;
+        press_with(&data, &RecordingResponder::default(), "join", HELPER).await;
+        press_with(&data, &RecordingResponder::default(), "start", PLAYER).await;
+        MinesweeperGames::of(&data).await.with(GAME, |game| {
+            game.board = MinesweeperBoard::with_mines(5, 5, mines);
+        });
+        data
+    }
+
+    #[tokio::test]
+    async fn coop_lobby_gathers_players() {
+        let (data, response) = start(json!([{"name": "coop", "type": 5, "value": true}])).await;
+        assert!(content(&response).starts_with("<@10> started a co-op game of minesweeper!"));
+        assert_eq!(
+            button_ids(&response),
+            vec![vec![
+                "minesweeper-1-join",
+                "minesweeper-1-start",
+                "minesweeper-1-custom"
+            ]]
+        );
+
+        let response = press_with(&data, &RecordingResponder::default(), "join", HELPER).await;
+        assert!(content(&response).contains("\nPlayers: <@10>, <@20>\n"));
+        let response = press_with(&data, &RecordingResponder::default(), "join", HELPER).await;
+        assert_eq!(content(&response), "You're already in this game!");
+
+        let response = press_with(&data, &RecordingResponder::default(), "start", HELPER).await;
+        assert_eq!(
+            content(&response),
+            "Only the player who started this game can do that."
+        );
+        let response = press_with(&data, &RecordingResponder::default(), "start", PLAYER).await;
+        assert_eq!(
+            content(&response),
+            "Clearing the board together: <@10>, <@20>"
+        );
+        assert_eq!(button_ids(&response).len(), 5);
+    }
+
+    #[tokio::test]
+    async fn coop_players_are_credited() {
+        let data = coop_game(&[0]).await;
+
+        press(&data, 1, HELPER).await;
+        let response = press(&data, 24, PLAYER).await;
+
+        assert!(content(&response).starts_with("**You win!**"));
+        assert!(content(&response).ends_with("\nCleared by: <@10> 23, <@20> 1"));
+        assert!(press(&data, 2, UserId::new(30))
+            .await
+            .to_string()
+            .contains("Thats not your game!"));
+    }
+
+    #[tokio::test]
+    async fn coop_clicks_that_come_too_late_are_refused() {
+        let data = coop_game(&[0]).await;
+
+        press(&data, 1, PLAYER).await;
+        let response = press(&data, 1, HELPER).await;
+        assert_eq!(content(&response), "That tile has already been cleared!");
+        assert_eq!(response["data"]["flags"], 64);
+
+        press(&data, 24, PLAYER).await;
+        let response = press(&data, 2, HELPER).await;
+        assert_eq!(content(&response), "This game is already over!");
+    }
+
+    #[tokio::test]
+    async fn coop_names_who_hit_a_mine() {
+        let data = coop_game(&[0]).await;
+
+        press(&data, 1, PLAYER).await;
+        let response = press(&data, 0, HELPER).await;
+
+        assert!(content(&response).ends_with("\nCleared by: <@10> 1, <@20> 0\n<@20> hit a mine."));
+    }
+
+    #[tokio::test]
+    async fn invited_games_are_reserved() {
+        let data = games(None);
+        let responder = RecordingResponder::default();
+        let command = command_interaction(
+            "minesweeper",
+            PLAYER,
+            1,
+            json!([{ "name": "invite", "type": 6, "value": "20" }]),
+            json!({ "users": { "20": {
+                "id": "20",
+                "username": "helper",
+                "discriminator": "0001",
+                "avatar": null,
+            } } }),
+        );
+        minesweeper(&data, &responder, &command).await.unwrap();
+        let response = responder.last();
+        assert!(content(&response).starts_with("<@10> invited <@20>"));
+        assert_eq!(response["data"]["allowed_mentions"]["users"], json!(["20"]));
+
+        let response = press(&data, 0, UserId::new(30)).await;
+        assert!(content(&response).starts_with("Thats not your game!"));
+        let response = press_with(
+            &data,
+            &RecordingResponder::default(),
+            "join",
+            UserId::new(30),
+        )
+        .await;
+        assert!(content(&response).starts_with("This game is by invitation only!"));
+        let response = press_with(&data, &RecordingResponder::default(), "join", HELPER).await;
+        assert_eq!(response["type"], 7);
+    }
+
+    /// A race `HELPER` has joined, started with mines at `mines` and nothing
+    /// cleared for everyone.
+    async fn race_game(mines: &[usize]) -> RwLock<TypeMap> {
+        let (data, _) = start(json!([{"name": "race", "type": 5, "value": true}])).await;
+        press_with(&data, &RecordingResponder::default(), "join", HELPER).await;
+        press_with(&data, &RecordingResponder::default(), "start", PLAYER).await;
+        MinesweeperGames::of(&data).await.with(GAME, |game| {
+            game.board = MinesweeperBoard::with_mines(5, 5, mines);
+            for racer in game.racers.values_mut() {
+                racer.board = MinesweeperBoard::with_mines(5, 5, mines);
+            }
+        });
+        data
+    }
+
+    /// The last edit to the race's message in the channel.
+    fn scoreboard(responder: &RecordingResponder) -> Value {
+        let (token, edit) = responder.edits_to().pop().expect("No edit was sent");
+        assert_eq!(token, "token");
+        edit
+    }
+
+    #[tokio::test]
+    async fn race_gives_everyone_the_same_board() {
+        let (data, response) = start(json!([{"name": "race", "type": 5, "value": true}])).await;
+        assert!(content(&response).starts_with("<@10> started a minesweeper race!"));
+        let response = press_with(&data, &RecordingResponder::default(), "start", PLAYER).await;
+        assert!(content(&response).starts_with("A race needs at least two players!"));
+
+        press_with(&data, &RecordingResponder::default(), "join", HELPER).await;
+        let response = press_with(&data, &RecordingResponder::default(), "start", PLAYER).await;
+        let lines = content(&response).lines().collect::<Vec<_>>();
+        assert_eq!(
+            lines[0],
+            "**The race is on!** Press Play to get your board."
+        );
+        assert!(lines[1].starts_with("<@10>: ") && lines[1].ends_with("/22 cleared"));
+        assert_eq!(lines[1].replace("<@10>", "<@20>"), lines[2]);
+        assert_eq!(lines[3], "Level: Beginner");
+        assert_eq!(button_ids(&response), vec![vec!["minesweeper-1-play"]]);
+
+        let response = press_with(&data, &RecordingResponder::default(), "play", HELPER).await;
+        assert_eq!(response["data"]["flags"], 64);
+        assert_eq!(button_ids(&response).len(), 5);
+
+        // Everyone races on the same mines, with the middle already cleared
+        let game = stored_game(&data).await;
+        let board = &game.racers[&PLAYER].board;
+        let cells = board.cells().unwrap();
+        assert_eq!(game.racers[&HELPER].board.cells(), Some(cells));
+        let mines = cells.iter().filter(|&&c| c == MinesweeperCell::Bomb);
+        assert_eq!(mines.count(), 3);
+        assert_eq!(cells[12], MinesweeperCell::Checked);
+        for index in board.adjacent_indexes(12) {
+            assert_ne!(cells[index], MinesweeperCell::Bomb);
+        }
+        let response = press_with(
+            &data,
+            &RecordingResponder::default(),
+            "play",
+            UserId::new(30),
+        )
+        .await;
+        assert!(content(&response).starts_with("Thats not your game!"));
+    }
+
+    #[tokio::test]
+    async fn race_scoreboard_names_the_winner() {
+        let data = race_game(&[0]).await;
+
+        let responder = RecordingResponder::default();
+        let response = press_with(&data, &responder, "1", PLAYER).await;
+        assert_eq!(response["type"], 7);
+        assert!(scoreboard(&responder)["content"]
+            .as_str()
+            .unwrap()
+            .contains("\n<@10>: 1/24 cleared\n"));
+
+        let responder = RecordingResponder::default();
+        press_with(&data, &responder, "24", HELPER).await;
+        let edit = scoreboard(&responder);
+        assert_eq!(
+            edit["content"],
+            "**<@20> wins the race!**\n\
+             <@10>: 1/24 cleared\n\
+             <@20>: cleared in 0.0s\n\
+             Level: Custom 5x5"
+        );
+        assert_eq!(edit["components"], json!([]));
+        assert!(stored_game(&data).await.is_over());
+
+        let response = press(&data, 2, PLAYER).await;
+        assert!(content(&response).starts_with("This race is over!"));
+    }
+
+    #[tokio::test]
+    async fn race_can_end_without_a_winner() {
+        let data = race_game(&[0]).await;
+
+        press(&data, 0, PLAYER).await;
+        assert!(!stored_game(&data).await.is_over());
+        let responder = RecordingResponder::default();
+        press_with(&data, &responder, "0", HELPER).await;
+
+        assert!(scoreboard(&responder)["content"]
+            .as_str()
+            .unwrap()
+            .starts_with(
+                "**Everyone hit a mine!** Nobody wins this race.\n\
+             <@10>: hit a mine after 0.0s, 0/24 cleared\n"
+            ));
+    }
+
+    #[tokio::test]
+    async fn custom_game_form_changes_the_board() {
+        let (data, _) = start(json!([{"name": "coop", "type": 5, "value": true}])).await;
+        let response = press_with(&data, &RecordingResponder::default(), "custom", PLAYER).await;
+        assert_eq!(response["type"], 9);
+        assert_eq!(response["data"]["custom_id"], "minesweeper-setup-1");
+
+        let submit = |values: &'static [(&'static str, &'static str)]| {
+            let data = &data;
+            async move {
+                let responder = RecordingResponder::default();
+                let modal = modal_interaction("minesweeper-setup-1", PLAYER, values);
+                minesweeper_setup(data, &responder, &modal).await.unwrap();
+                responder.last()
+            }
+        };
+        let response = submit(&[("width", "4"), ("height", "3"), ("mines", "12")]).await;
+        assert!(content(&response).starts_with("A 4x3 board has room for at most 11 mines"));
+        let response = submit(&[("width", "4"), ("height", "3"), ("mines", "2")]).await;
+        assert!(content(&response).ends_with("\nLevel: Custom 4x3"));
+        assert_eq!(stored_game(&data).await.board.mines(), 2);
+    }
+
+    /// Plays a new game with a mine in the corner, clearing the board
+    /// `millis` after the first click.
+    async fn win_in(data: &RwLock<TypeMap>, millis: u64) -> Value {
+        let mut game = MinesweeperGame::new(PLAYER, 1);
+        game.board = MinesweeperBoard::with_mines(5, 5, &[0]);
+        MinesweeperGames::of(data).await.insert(GAME, game);
+        click_at(data, 1, 5_000).await;
+        click_at(data, 24, 5_000 + millis).await
+    }
+
+    #[tokio::test]
+    async fn wins_are_timed_between_first_and_last_click() {
+        let data = games(None);
+
+        let response = win_in(&data, 12_345).await;
+
+        assert!(content(&response).contains("\nTime: 12.345s\n"));
+        assert!(!content(&response).contains("best"));
+    }
+
+    #[tokio::test]
+    async fn wins_are_compared_with_personal_bests() {
+        let data = games(None);
+        win_in(&data, 12_345).await;
+
+        let response = win_in(&data, 11_145).await;
+        assert!(content(&response).ends_with("\nNew personal best by 1.2s!"));
+        let response = win_in(&data, 20_000).await;
+        assert!(content(&response).ends_with("\nPersonal best: 11.145s"));
+    }
+
+    #[tokio::test]
+    async fn one_click_wins_arent_records() {
+        let data = games(None);
+        let mut game = MinesweeperGame::new(PLAYER, 1);
+        game.board = MinesweeperBoard::with_mines(5, 5, &[0]);
+        MinesweeperGames::of(&data).await.insert(GAME, game);
+
+        let response = click_at(&data, 24, 5_000).await;
+        assert!(content(&response).starts_with("**You win!**"));
+        assert!(content(&response).contains("\nTime: 0.0s\n"));
+
+        // The next timed win is still the first record
+        let response = win_in(&data, 30_000).await;
+        assert!(!content(&response).contains("best"));
+        let response = win_in(&data, 29_000).await;
+        assert!(content(&response).ends_with("\nNew personal best by 1.0s!"));
+    }
+
+    #[tokio::test]
+    async fn first_click_is_safe() {
+        let data = games(Some(MinesweeperGame::new(PLAYER, 23)));
+
+        let response = press(&data, 12, PLAYER).await;
+
+        assert_eq!(response["type"], 7);
+        assert_eq!(
+            response["data"]["components"][2]["components"][2]["disabled"],
+            true
+        );
+        let board = stored_game(&data).await.board;
+        assert_eq!(board.cells().unwrap()[12], MinesweeperCell::Checked);
+    }
+
+    #[tokio::test]
+    async fn mine_ends_game() {
+        let data = games(Some(game_with_mines(&[0, 24])));
+
+        let responder = RecordingResponder::default();
+        let response = press_with(&data, &responder, "0", PLAYER).await;
+
+        assert!(content(&response).starts_with("**Game over.**\nMines: 2\nCleared: 0/23"));
+        assert!(stored_game(&data).await.board.is_over());
+        let followups = responder.followups();
+        assert_eq!(
+            followups[0]["components"][0]["components"][0]["custom_id"],
+            "minesweeper-1-replay"
+        );
+    }
+
+    #[tokio::test]
+    async fn replay_deals_the_same_board() {
+        let mut game = MinesweeperGame::new(PLAYER, 3);
+        game.board = MinesweeperBoard::with_seed(5, 5, 3, 42);
+        let data = games(Some(game));
+        press(&data, 12, PLAYER).await;
+        let final_board = render_text_board(&stored_game(&data).await.board);
+
+        let responder = RecordingResponder::default();
+        let response = press_with(&data, &responder, "replay", UserId::new(20)).await;
+
+        assert_eq!(response["data"]["flags"], 64);
+        assert!(content(&response).starts_with("**Replay:** start of game (1 move)"));
+        let export = String::from_utf8(responder.files()[0].data.clone()).unwrap();
+        assert!(export.contains("[Mines \"3\"]\n[Seed \"42\"]\n"));
+        assert!(export.ends_with("\n\n1. c3 {+0.0s} *\n"));
+
+        let response = press_with(&data, &responder, "step-1", UserId::new(20)).await;
+        assert_eq!(
+            content(&response),
+            format!("**Replay:** move 1 of 1: c3\n\n{}", final_board)
+        );
+    }
+
+    #[tokio::test]
+    async fn broken_replays_are_refused() {
+        let mut game = MinesweeperGame::new(PLAYER, 3);
+        game.board = MinesweeperBoard::with_seed(5, 5, 3, 42);
+        let data = games(Some(game));
+        let response = press(&data, 0, PLAYER).await;
+        assert_eq!(response["type"], 7);
+        MinesweeperGames::of(&data)
+            .await
+            .with(GAME, |game| game.history.clear());
+
+        let response = press_with(&data, &RecordingResponder::default(), "replay", PLAYER).await;
+        assert_eq!(
+            content(&response),
+            "There's nothing to replay in this game."
+        );
+
+        // Steps past the end show the last move
+        press(&data, 12, PLAYER).await;
+        let response = press_with(&data, &RecordingResponder::default(), "step-99", PLAYER).await;
+        assert!(content(&response).starts_with("**Replay:** move 1 of 1"));
+
+        // A move that doesn't play out again, instead of panicking
+        MinesweeperGames::of(&data).await.with(GAME, |game| {
+            game.history
+                .push(MoveRecord::now(MinesweeperAction::Reveal(99)))
+        });
+        let response = press_with(&data, &RecordingResponder::default(), "step-2", PLAYER).await;
+        assert_eq!(
+            content(&response),
+            "There's nothing to replay in this game."
+        );
+        assert_eq!(stored_game(&data).await.history.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn clearing_board_wins() {
+        let data = games(Some(game_with_mines(&[0])));
+
+        let response = press(&data, 24, PLAYER).await;
+
+        assert!(content(&response).starts_with("**You win!**\nMines: 1"));
+        assert!(content(&response).ends_with("\nLevel: Custom 5x5"));
+    }
+
+    #[tokio::test]
+    async fn other_players_are_rejected() {
+        let data = games(Some(game_with_mines(&[0])));
+
+        let response = press(&data, 24, UserId::new(20)).await;
+
+        assert!(content(&response).starts_with("Thats not your game!"));
+        assert_eq!(response["data"]["flags"], 64);
+    }
+
+    #[tokio::test]
+    async fn missing_game_has_expired() {
+        let data = games(None);
+
+        let response = press(&data, 0, PLAYER).await;
+
+        assert!(content(&response).starts_with("This game has expired"));
+    }
+}
@@ -1,6 +1,145 @@
 pub mod error;
+pub mod help;
 pub mod ping;
+pub mod replay;
+pub mod responder;
+pub mod setup;
 
 pub mod blackjack;
 pub mod minesweeper;
 pub mod tictactoe;
+
+use serenity::builder::{CreateCommand, CreateCommandOption};
+use serenity::json::Value;
+use serenity::model::application::CommandOptionType;
+use serenity::model::Permissions;
+
+use crate::commands::minesweeper::Preset;
+use crate::games::minesweeper::MinesweeperBoard;
+use crate::locale::{localizations, Locale, Localized};
+
+/// Every slash command, with descriptions and translations from the
+/// `command-*` and `option-*` messages. `/help` is last, offering every
+/// other command everyone can use as a choice.
+pub fn register() -> Vec<CreateCommand> {
+    let mut preset = CreateCommandOption::new(CommandOptionType::String, "preset", "")
+        .localized("option-minesweeper-preset");
+    for level in Preset::ALL {
+        let id = format!("minesweeper-{}", level.name());
+        preset = preset.add_string_choice_localized(
+            Locale::default().text(&id, None),
+            level.name(),
+            localizations(&id),
+        );
+    }
+
+    let mut commands = vec![
+        CreateCommand::new("ping").localized("command-ping"),
+        CreateCommand::new("minesweeper")
+            .localized("command-minesweeper")
+            .add_option(preset)
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::Integer, "width", "")
+                    .localized("option-minesweeper-width")
+                    .min_int_value(3)
+                    .max_int_value(5),
+            )
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::Integer, "height", "")
+                    .localized("option-minesweeper-height")
+                    .min_int_value(3)
+                    .max_int_value(5),
+            )
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::Integer, "mines", "")
+                    .localized("option-minesweeper-mines")
+                    .min_int_value(1)
+                    // The most any board can take, leaving `/minesweeper`
+                    // to say how many fit on the one asked for
+                    .max_int_value(MinesweeperBoard::max_mines(5, 5, false) as u64),
+            )
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::Boolean, "opening", "")
+                    .localized("option-minesweeper-opening"),
+            )
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::Boolean, "coop", "")
+                    .localized("option-minesweeper-coop"),
+            )
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::User, "invite", "")
+                    .localized("option-minesweeper-invite"),
+            )
+            // A flag rather than a `race` subcommand: a command with
+            // subcommands can't be run on its own, so plain `/minesweeper`
+            // would have to become `/minesweeper play`, and every board
+            // option would have to be repeated under each subcommand
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::Boolean, "race", "")
+                    .localized("option-minesweeper-race"),
+            ),
+        CreateCommand::new("blackjack").localized("command-blackjack"),
+        CreateCommand::new("tictactoe")
+            .localized("command-tictactoe")
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::Integer, "size", "")
+                    .localized("option-tictactoe-size")
+                    .min_int_value(2)
+                    .max_int_value(5),
+            )
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::User, "opponent", "")
+                    .localized("option-tictactoe-opponent"),
+            )
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::Integer, "move_time", "")
+                    .localized("option-tictactoe-move_time")
+                    .min_int_value(10)
+                    .max_int_value(600),
+            ),
+        // Only there to try out error reporting
+        CreateCommand::new("error")
+            .localized("command-error")
+            .default_member_permissions(Permissions::ADMINISTRATOR),
+    ];
+
+    let mut command = CreateCommandOption::new(CommandOptionType::String, "command", "")
+        .localized("option-help-command");
+    for definition in commands.iter().map(definition).filter(is_public) {
+        let name = definition["name"].as_str().unwrap_or_default().to_string();
+        let localizations = definition["name_localizations"]
+            .as_object()
+            .into_iter()
+            .flatten()
+            .map(|(locale, name)| {
+                (
+                    locale.clone(),
+                    name.as_str().unwrap_or_default().to_string(),
+                )
+            })
+            .collect::<Vec<_>>();
+        command = command.add_string_choice_localized(name.clone(), name, localizations);
+    }
+    commands.push(
+        CreateCommand::new("help")
+            .localized("command-help")
+            .add_option(command),
+    );
+    commands
+}
+
+/// The JSON Discord is sent for a command.
+fn definition(command: &CreateCommand) -> Value {
+    serde_json::to_value(command).unwrap_or_default()
+}
+
+/// Whether everyone can use a command, rather than only members with some
+/// permission, going by its JSON.
+pub fn is_public(definition: &Value) -> bool {
+    definition["default_member_permissions"].is_null()
+}
+
+/// The JSON of every command `register` sends to Discord.
+pub fn definitions() -> Vec<Value> {
+    register().iter().map(definition).collect()
+}
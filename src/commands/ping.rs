@@ -1,19 +1,18 @@
-use serenity::model::interactions::{
-    application_command::ApplicationCommandInteraction, InteractionResponseType,
-};
-use serenity::prelude::*;
+use serenity::builder::{CreateInteractionResponse, CreateInteractionResponseMessage};
+use serenity::model::application::CommandInteraction;
 
 use tracing::info;
 
+use crate::commands::responder::Responder;
+use crate::locale::{tr, Locale};
 use crate::Error;
 
-pub async fn ping(ctx: &Context, command: &ApplicationCommandInteraction) -> Result<(), Error> {
-    command
-        .create_interaction_response(&ctx.http, |response| {
-            response
-                .kind(InteractionResponseType::ChannelMessageWithSource)
-                .interaction_response_data(|message| message.content("Pong!"))
-        })
+pub async fn ping(responder: &impl Responder, command: &CommandInteraction) -> Result<(), Error> {
+    let locale = Locale::of_guild(command.guild_locale.as_deref(), &command.locale);
+    responder
+        .create_interaction_response(CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new().content(tr!(locale, "pong")),
+        ))
         .await?;
 
     info!(
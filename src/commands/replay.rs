@@ -0,0 +1,170 @@
+//! Move histories of finished games, shown step by step or exported as text.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use serenity::builder::{CreateActionRow, CreateAttachment, CreateButton};
+use serenity::model::application::ButtonStyle;
+use serenity::model::id::InteractionId;
+
+use crate::locale::{tr, Locale};
+
+/// Milliseconds from the Unix epoch to the start of 2015, where Discord's ids
+/// start counting.
+const DISCORD_EPOCH: u64 = 1_420_070_400_000;
+
+/// A move and when it was made.
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct MoveRecord<A> {
+    pub action: A,
+    pub time: SystemTime,
+}
+
+impl<A> MoveRecord<A> {
+    pub fn now(action: A) -> MoveRecord<A> {
+        MoveRecord::at(action, SystemTime::now())
+    }
+
+    pub fn at(action: A, time: SystemTime) -> MoveRecord<A> {
+        MoveRecord { action, time }
+    }
+}
+
+/// When Discord received the interaction `id`, to the millisecond. Unlike the
+/// time the bot got around to it, this leaves out delays on the way.
+pub fn click_time(id: InteractionId) -> SystemTime {
+    UNIX_EPOCH + Duration::from_millis((id.get() >> 22) + DISCORD_EPOCH)
+}
+
+/// `time` in seconds, down to the millisecond but without trailing zeros.
+pub fn format_seconds(time: Duration) -> String {
+    let seconds = format!("{:.3}", time.as_secs_f64());
+    let trimmed = seconds.trim_end_matches('0');
+    match trimmed.strip_suffix('.') {
+        Some(whole) => format!("{}.0", whole),
+        None => trimmed.to_string(),
+    }
+}
+
+/// Name of a cell in algebraic notation, with `a1` in the top left corner.
+pub fn cell_name(index: usize, width: usize) -> String {
+    let column = (b'a' + (index % width) as u8) as char;
+    format!("{}{}", column, index / width + 1)
+}
+
+/// Annotates each move with the seconds taken to make it, to the millisecond.
+pub fn timed_moves<A>(
+    start: SystemTime,
+    history: &[MoveRecord<A>],
+    name: impl Fn(&A) -> String,
+) -> Vec<String> {
+    let mut previous = start;
+    history
+        .iter()
+        .map(|record| {
+            let taken = record.time.duration_since(previous).unwrap_or_default();
+            previous = record.time;
+            format!("{} {{+{}s}}", name(&record.action), format_seconds(taken))
+        })
+        .collect()
+}
+
+/// A PGN-like record of a game: tag pairs, then the numbered turns followed
+/// by the result.
+pub fn export(tags: &[(&str, String)], turns: &[String], result: &str) -> String {
+    let mut text = tags
+        .iter()
+        .map(|(name, value)| format!("[{} \"{}\"]\n", name, value))
+        .collect::<String>();
+    text.push('\n');
+    for (number, turn) in turns.iter().enumerate() {
+        text.push_str(&format!("{}. {} ", number + 1, turn));
+    }
+    text.push_str(result);
+    text.push('\n');
+    text
+}
+
+pub fn unix_time(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).unwrap().as_secs()
+}
+
+pub fn export_file(filename: String, text: String) -> CreateAttachment {
+    CreateAttachment::bytes(text, filename)
+}
+
+/// Previous and Next buttons for stepping through `moves` moves. `prefix`
+/// followed by the step to show makes up their custom ids.
+pub fn render_steps(prefix: &str, step: usize, moves: usize, locale: Locale) -> CreateActionRow {
+    CreateActionRow::Buttons(vec![
+        CreateButton::new(format!("{}-{}", prefix, step.saturating_sub(1)))
+            .label(tr!(locale, "replay-previous"))
+            .style(ButtonStyle::Secondary)
+            .disabled(step == 0),
+        CreateButton::new(format!("{}-{}", prefix, step + 1))
+            .label(tr!(locale, "replay-next"))
+            .style(ButtonStyle::Primary)
+            .disabled(step >= moves),
+    ])
+}
+
+/// Heading of a replay at `step`.
+pub fn step_heading(step: usize, moves: usize, locale: Locale) -> String {
+    match step {
+        0 => tr!(locale, "replay-start", moves = moves),
+        step => tr!(locale, "replay-move", step = step, moves = moves),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn cells_are_named_from_top_left() {
+        assert_eq!(cell_name(0, 3), "a1");
+        assert_eq!(cell_name(5, 3), "c2");
+        assert_eq!(cell_name(24, 5), "e5");
+    }
+
+    #[test]
+    fn click_time_comes_from_the_id() {
+        // Discord's example snowflake, made on 2016-04-30 11:18:25.796 UTC
+        let id = InteractionId::new(175928847299117063);
+        assert_eq!(
+            click_time(id),
+            UNIX_EPOCH + Duration::from_millis(1462015105796)
+        );
+    }
+
+    #[test]
+    fn seconds_keep_milliseconds() {
+        assert_eq!(format_seconds(Duration::from_millis(12345)), "12.345");
+        assert_eq!(format_seconds(Duration::from_millis(1200)), "1.2");
+        assert_eq!(format_seconds(Duration::from_millis(40)), "0.04");
+        assert_eq!(format_seconds(Duration::from_secs(7)), "7.0");
+    }
+
+    #[test]
+    fn export_numbers_turns() {
+        let start = UNIX_EPOCH;
+        let history = [
+            MoveRecord {
+                action: 4,
+                time: start + Duration::from_millis(2250),
+            },
+            MoveRecord {
+                action: 0,
+                time: start + Duration::from_secs(7),
+            },
+        ];
+        let moves = timed_moves(start, &history, |&i| cell_name(i, 3));
+
+        assert_eq!(
+            export(&[("Game", String::from("Test"))], &moves, "1-0"),
+            "[Game \"Test\"]\n\n1. b2 {+2.25s} 2. a1 {+4.75s} 1-0\n"
+        );
+    }
+}
@@ -0,0 +1,395 @@
+use std::sync::Arc;
+
+use serenity::async_trait;
+use serenity::builder::{
+    CreateAttachment, CreateInteractionResponse, CreateInteractionResponseFollowup,
+    EditInteractionResponse,
+};
+use serenity::http::Http;
+use serenity::json::Value;
+use serenity::model::id::InteractionId;
+
+use crate::Error;
+
+/// A file attached to a response.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ResponseFile {
+    pub filename: String,
+    pub data: Vec<u8>,
+}
+
+impl From<CreateAttachment> for ResponseFile {
+    fn from(file: CreateAttachment) -> ResponseFile {
+        ResponseFile {
+            filename: file.filename,
+            data: file.data,
+        }
+    }
+}
+
+/// Sends responses to an interaction.
+///
+/// Handlers respond through this instead of the interaction itself so they can
+/// be driven in tests without a connection to Discord. Responders can be
+/// cloned into background tasks that edit the response later on.
+#[async_trait]
+pub trait Responder: Clone + Send + Sync + 'static {
+    async fn send_response(&self, response: Value, files: Vec<ResponseFile>) -> Result<(), Error>;
+
+    async fn send_edit(&self, edit: Value) -> Result<(), Error>;
+
+    /// Edits the message sent in response to another interaction, given its
+    /// token.
+    async fn send_edit_to(&self, token: &str, edit: Value) -> Result<(), Error>;
+
+    async fn send_followup(&self, followup: Value) -> Result<(), Error>;
+
+    async fn create_interaction_response(
+        &self,
+        response: CreateInteractionResponse,
+    ) -> Result<(), Error> {
+        self.create_interaction_response_with_files(response, Vec::new())
+            .await
+    }
+
+    /// Responds with `files` attached. They are kept apart from the response
+    /// since builders don't give their files back.
+    async fn create_interaction_response_with_files(
+        &self,
+        response: CreateInteractionResponse,
+        files: Vec<CreateAttachment>,
+    ) -> Result<(), Error> {
+        let response = serde_json::to_value(&response)?;
+        let files = files.into_iter().map(ResponseFile::from).collect();
+        self.send_response(response, files).await
+    }
+
+    /// Edits the message sent in response to the interaction.
+    async fn edit_original_interaction_response(
+        &self,
+        edit: EditInteractionResponse,
+    ) -> Result<(), Error> {
+        self.send_edit(serde_json::to_value(&edit)?).await
+    }
+
+    async fn edit_interaction_response(
+        &self,
+        token: &str,
+        edit: EditInteractionResponse,
+    ) -> Result<(), Error> {
+        self.send_edit_to(token, serde_json::to_value(&edit)?).await
+    }
+
+    /// Sends another message after the response to the interaction.
+    async fn create_followup_message(
+        &self,
+        followup: CreateInteractionResponseFollowup,
+    ) -> Result<(), Error> {
+        self.send_followup(serde_json::to_value(&followup)?).await
+    }
+}
+
+/// Responds to an interaction over Discord's HTTP API.
+#[derive(Clone)]
+pub struct InteractionResponder {
+    http: Arc<Http>,
+    id: InteractionId,
+    token: String,
+}
+
+impl InteractionResponder {
+    pub fn new(http: Arc<Http>, id: InteractionId, token: String) -> InteractionResponder {
+        InteractionResponder { http, id, token }
+    }
+}
+
+#[async_trait]
+impl Responder for InteractionResponder {
+    async fn send_response(&self, response: Value, files: Vec<ResponseFile>) -> Result<(), Error> {
+        let files = files
+            .into_iter()
+            .map(|file| CreateAttachment::bytes(file.data, file.filename))
+            .collect();
+        self.http
+            .create_interaction_response(self.id, &self.token, &response, files)
+            .await?;
+        Ok(())
+    }
+
+    async fn send_edit(&self, edit: Value) -> Result<(), Error> {
+        self.send_edit_to(&self.token, edit).await
+    }
+
+    async fn send_edit_to(&self, token: &str, edit: Value) -> Result<(), Error> {
+        self.http
+            .edit_original_interaction_response(token, &edit, Vec::new())
+            .await?;
+        Ok(())
+    }
+
+    async fn send_followup(&self, followup: Value) -> Result<(), Error> {
+        self.http
+            .create_followup_message(&self.token, &followup, Vec::new())
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub mod testing {
+    use std::sync::Mutex;
+
+    use serenity::json::json;
+    use serenity::model::application::{
+        CommandInteraction, ComponentInteraction, ModalInteraction,
+    };
+    use serenity::model::id::UserId;
+
+    use super::*;
+
+    /// Keeps every response in memory so tests can assert on them. Clones
+    /// share the same recording.
+    #[derive(Clone, Default)]
+    pub struct RecordingResponder {
+        responses: Arc<Mutex<Vec<Value>>>,
+        files: Arc<Mutex<Vec<ResponseFile>>>,
+        edits: Arc<Mutex<Vec<Value>>>,
+        edits_to: Arc<Mutex<Vec<(String, Value)>>>,
+        followups: Arc<Mutex<Vec<Value>>>,
+    }
+
+    impl RecordingResponder {
+        pub fn responses(&self) -> Vec<Value> {
+            self.responses.lock().unwrap().clone()
+        }
+
+        pub fn last(&self) -> Value {
+            self.responses().pop().expect("No response was sent")
+        }
+
+        /// Files attached to any of the responses.
+        pub fn files(&self) -> Vec<ResponseFile> {
+            self.files.lock().unwrap().clone()
+        }
+
+        pub fn edits(&self) -> Vec<Value> {
+            self.edits.lock().unwrap().clone()
+        }
+
+        /// Edits to other interactions' responses, with their tokens.
+        pub fn edits_to(&self) -> Vec<(String, Value)> {
+            self.edits_to.lock().unwrap().clone()
+        }
+
+        pub fn followups(&self) -> Vec<Value> {
+            self.followups.lock().unwrap().clone()
+        }
+    }
+
+    #[async_trait]
+    impl Responder for RecordingResponder {
+        async fn send_response(
+            &self,
+            response: Value,
+            files: Vec<ResponseFile>,
+        ) -> Result<(), Error> {
+            self.responses.lock().unwrap().push(response);
+            self.files.lock().unwrap().extend(files);
+            Ok(())
+        }
+
+        async fn send_edit(&self, edit: Value) -> Result<(), Error> {
+            self.edits.lock().unwrap().push(edit);
+            Ok(())
+        }
+
+        async fn send_edit_to(&self, token: &str, edit: Value) -> Result<(), Error> {
+            self.edits_to
+                .lock()
+                .unwrap()
+                .push((token.to_string(), edit));
+            Ok(())
+        }
+
+        async fn send_followup(&self, followup: Value) -> Result<(), Error> {
+            self.followups.lock().unwrap().push(followup);
+            Ok(())
+        }
+    }
+
+    /// Content of a recorded response.
+    pub fn content(response: &Value) -> &str {
+        response["data"]["content"].as_str().unwrap_or_default()
+    }
+
+    /// Custom ids of every button in a recorded response, row by row.
+    pub fn button_ids(response: &Value) -> Vec<Vec<String>> {
+        response["data"]["components"]
+            .as_array()
+            .map(|rows| {
+                rows.iter()
+                    .map(|row| {
+                        row["components"]
+                            .as_array()
+                            .unwrap()
+                            .iter()
+                            .map(|button| button["custom_id"].as_str().unwrap().to_string())
+                            .collect()
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn user_json(id: UserId) -> Value {
+        json!({
+            "id": id.to_string(),
+            "username": format!("user{}", id),
+            "discriminator": "0001",
+            "avatar": null,
+        })
+    }
+
+    /// `/name` run by `user` with `options`. `resolved` holds the users,
+    /// roles or channels referenced by the options.
+    pub fn command_interaction(
+        name: &str,
+        user: UserId,
+        interaction_id: u64,
+        options: Value,
+        resolved: Value,
+    ) -> CommandInteraction {
+        serenity::json::from_value(json!({
+            "id": interaction_id.to_string(),
+            "application_id": "900000000000000002",
+            "type": 2,
+            "data": {
+                "id": "900000000000000006",
+                "name": name,
+                "type": 1,
+                "options": options,
+                "resolved": resolved,
+            },
+            "channel_id": "900000000000000003",
+            "user": user_json(user),
+            "token": "token",
+            "version": 1,
+            "locale": "en-US",
+            "entitlements": [],
+            "attachment_size_limit": 26214400,
+        }))
+        .unwrap()
+    }
+
+    /// The id of every button press made by [`component_interaction`].
+    pub const COMPONENT_ID: u64 = 900000000000000001;
+
+    /// A button press on a message created by the command `interaction_id`,
+    /// which was run by `owner`.
+    pub fn component_interaction(
+        custom_id: &str,
+        user: UserId,
+        interaction_id: u64,
+        owner: UserId,
+    ) -> ComponentInteraction {
+        serenity::json::from_value(json!({
+            "id": COMPONENT_ID.to_string(),
+            "application_id": "900000000000000002",
+            "type": 3,
+            "data": {
+                "custom_id": custom_id,
+                "component_type": 2,
+            },
+            "channel_id": "900000000000000003",
+            "user": user_json(user),
+            "token": "token",
+            "version": 1,
+            "locale": "en-US",
+            "entitlements": [],
+            "attachment_size_limit": 26214400,
+            "message": {
+                "id": "900000000000000004",
+                "channel_id": "900000000000000003",
+                "author": user_json(UserId::new(900000000000000005)),
+                "content": "",
+                "timestamp": "2022-01-01T00:00:00.000000+00:00",
+                "edited_timestamp": null,
+                "tts": false,
+                "mention_everyone": false,
+                "mentions": [],
+                "mention_roles": [],
+                "attachments": [],
+                "embeds": [],
+                "pinned": false,
+                "type": 20,
+                "interaction": {
+                    "id": interaction_id.to_string(),
+                    "type": 2,
+                    "name": "test",
+                    "user": user_json(owner),
+                },
+                "interaction_metadata": {
+                    "id": interaction_id.to_string(),
+                    "type": 2,
+                    "user": user_json(owner),
+                    "authorizing_integration_owners": {},
+                },
+            },
+        }))
+        .unwrap()
+    }
+
+    /// A button press on a message sent in answer to the button press
+    /// `interaction_id`, which was made by `owner`.
+    pub fn reply_component_interaction(
+        custom_id: &str,
+        user: UserId,
+        interaction_id: u64,
+        owner: UserId,
+    ) -> ComponentInteraction {
+        let mut component = component_interaction(custom_id, user, interaction_id, owner);
+        component.message.interaction_metadata = serenity::json::from_value(json!({
+            "id": interaction_id.to_string(),
+            "type": 3,
+            "user": user_json(owner),
+            "authorizing_integration_owners": {},
+            "interacted_message_id": "900000000000000004",
+        }))
+        .unwrap();
+        component
+    }
+
+    /// A form submitted by `user`, with `values` given for its inputs.
+    pub fn modal_interaction(
+        custom_id: &str,
+        user: UserId,
+        values: &[(&str, &str)],
+    ) -> ModalInteraction {
+        let rows = values
+            .iter()
+            .map(|(id, value)| {
+                json!({
+                    "type": 1,
+                    "components": [{"type": 4, "custom_id": id, "value": value}],
+                })
+            })
+            .collect::<Vec<_>>();
+        serenity::json::from_value(json!({
+            "id": "900000000000000001",
+            "application_id": "900000000000000002",
+            "type": 5,
+            "data": {
+                "custom_id": custom_id,
+                "components": rows,
+            },
+            "channel_id": "900000000000000003",
+            "user": user_json(user),
+            "token": "token",
+            "version": 1,
+            "locale": "en-US",
+            "entitlements": [],
+            "attachment_size_limit": 26214400,
+        }))
+        .unwrap()
+    }
+}
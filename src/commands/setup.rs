@@ -0,0 +1,146 @@
+//! "Custom game" buttons on game lobbies, which open a form for the options
+//! a slash command can't take comfortably.
+//!
+//! Submitting the form changes the lobby's settings rather than starting the
+//! game, since a lobby is still waiting for players: the game starts once
+//! they have joined, as usual. Solo minesweeper has no lobby, and so no
+//! form, as it starts as soon as `/minesweeper` is run.
+
+use serenity::builder::{CreateActionRow, CreateButton, CreateInputText, CreateModal};
+use serenity::model::application::{
+    ActionRowComponent, ButtonStyle, InputTextStyle, ModalInteraction,
+};
+
+use crate::locale::{tr, Locale};
+
+/// A number asked for on the form, labelled by the `setup-<id>` message.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Field {
+    pub id: &'static str,
+    pub min: usize,
+    pub max: usize,
+    /// Filled in to start with, usually the game's current setting.
+    pub value: usize,
+}
+
+/// The button that opens the form, pressed by the player who made the game.
+pub fn custom_button(custom_id: impl Into<String>, locale: Locale) -> CreateButton {
+    CreateButton::new(custom_id)
+        .label(tr!(locale, "setup-custom"))
+        .style(ButtonStyle::Secondary)
+}
+
+/// A form asking for every one of `fields`, submitted as `custom_id`.
+pub fn modal(custom_id: impl Into<String>, fields: &[Field], locale: Locale) -> CreateModal {
+    let rows = fields
+        .iter()
+        .map(|field| {
+            let input = CreateInputText::new(
+                InputTextStyle::Short,
+                tr!(locale, &format!("setup-{}", field.id)),
+                field.id,
+            )
+            .placeholder(format!("{}-{}", field.min, field.max))
+            .value(field.value.to_string())
+            .max_length(field.max.to_string().len() as u16);
+            CreateActionRow::InputText(input)
+        })
+        .collect();
+    CreateModal::new(custom_id, tr!(locale, "setup-title")).components(rows)
+}
+
+/// The numbers given for `fields` on a submitted form, in the same order, or
+/// what is wrong with them, one line per field.
+pub fn read<const N: usize>(
+    modal: &ModalInteraction,
+    fields: &[Field; N],
+    locale: Locale,
+) -> Result<[usize; N], String> {
+    let input = |id: &str| {
+        modal
+            .data
+            .components
+            .iter()
+            .flat_map(|row| &row.components)
+            .find_map(|component| match component {
+                ActionRowComponent::InputText(input) if input.custom_id == id => {
+                    input.value.as_deref()
+                }
+                _ => None,
+            })
+            .unwrap_or_default()
+            .trim()
+    };
+
+    let mut values = [0; N];
+    let mut errors = Vec::new();
+    for (value, field) in values.iter_mut().zip(fields) {
+        let name = tr!(locale, &format!("setup-{}", field.id));
+        match input(field.id).parse::<usize>() {
+            Ok(given) if (field.min..=field.max).contains(&given) => *value = given,
+            Ok(_) => errors.push(tr!(
+                locale,
+                "setup-out-of-range",
+                field = name,
+                min = field.min,
+                max = field.max
+            )),
+            Err(_) => errors.push(tr!(locale, "setup-not-a-number", field = name)),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(values)
+    } else {
+        Err(errors.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serenity::model::id::UserId;
+
+    use super::*;
+    use crate::commands::responder::testing::*;
+
+    const FIELDS: [Field; 2] = [
+        Field {
+            id: "size",
+            min: 2,
+            max: 5,
+            value: 3,
+        },
+        Field {
+            id: "move_time",
+            min: 10,
+            max: 600,
+            value: 60,
+        },
+    ];
+
+    #[test]
+    fn reads_every_field() {
+        let modal = modal_interaction(
+            "tictactoe-setup-1",
+            UserId::new(10),
+            &[("size", " 4 "), ("move_time", "30")],
+        );
+        assert_eq!(read(&modal, &FIELDS, Locale::default()), Ok([4, 30]));
+    }
+
+    #[test]
+    fn explains_every_mistake() {
+        let modal = modal_interaction(
+            "tictactoe-setup-1",
+            UserId::new(10),
+            &[("size", "9"), ("move_time", "soon")],
+        );
+        assert_eq!(
+            read(&modal, &FIELDS, Locale::default()),
+            Err(String::from(
+                "**Board size** must be between 2 and 5.\n\
+                 **Seconds per move** must be a whole number."
+            ))
+        );
+    }
+}
@@ -1,394 +1,2015 @@
 use std::collections::HashMap;
-use std::iter::repeat_with;
-use std::time::Instant;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use serenity::model::interactions::application_command::ApplicationCommandInteractionDataOptionValue;
-use serenity::prelude::*;
-use serenity::{
-    builder::CreateComponents,
-    model::{
-        channel::ReactionType,
-        id::{InteractionId, UserId},
-        interactions::{
-            application_command::ApplicationCommandInteraction,
-            message_component::{ButtonStyle, MessageComponentInteraction},
-            InteractionResponseType,
-        },
-    },
+use serde::{Deserialize, Serialize};
+
+use serenity::builder::{
+    CreateActionRow, CreateAllowedMentions, CreateAttachment, CreateButton,
+    CreateInteractionResponse, CreateInteractionResponseFollowup, CreateInteractionResponseMessage,
+    CreateModal, EditInteractionResponse,
 };
+use serenity::model::application::{
+    ButtonStyle, CommandInteraction, ComponentInteraction, MessageCommandInteractionMetadata,
+    MessageInteractionMetadata, ModalInteraction, ResolvedValue,
+};
+use serenity::model::channel::ReactionType;
+use serenity::model::id::UserId;
+use serenity::prelude::*;
 
+use crate::commands::replay::{
+    cell_name, export, export_file, render_steps, step_heading, timed_moves, unix_time, MoveRecord,
+};
+use crate::commands::responder::Responder;
+use crate::commands::setup::{custom_button, modal, read, Field};
+use crate::games::tictactoe::{
+    apply_move, TictactoeAction, TictactoeBoard, TictactoeCell, TictactoeOutcome,
+};
+use crate::games::MoveError;
+use crate::locale::{tr, Locale};
+use crate::store::{Game, GameId, GameStore};
 use crate::Error;
 
-#[derive(Clone, Copy, PartialEq, Debug)]
-pub enum TictactoeCell {
-    X,
-    O,
+use tracing::{error, Instrument};
+
+/// Discord allows at most this many action rows on a message.
+const MAX_ROWS: usize = 5;
+
+/// How long a challenged player has to accept before the challenge is
+/// cancelled.
+const CHALLENGE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How long each player has to make a move, unless set with `move_time`.
+const DEFAULT_MOVE_TIME: Duration = Duration::from_secs(60);
+
+fn render_cell(cell: TictactoeCell) -> String {
+    String::from(match cell {
+        TictactoeCell::X => "\u{274C}",
+        TictactoeCell::O => "\u{2B55}",
+    })
 }
 
-impl TictactoeCell {
-    fn render(&self) -> String {
-        String::from(match &self {
-            TictactoeCell::X => "\u{274C}",
-            TictactoeCell::O => "\u{2B55}",
-        })
+/// Why a game ended.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+enum Termination {
+    Normal,
+    Resignation,
+    TimeForfeit,
+    Agreement,
+}
+
+/// How a game ended.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+enum TictactoeResult {
+    Win(TictactoeCell, Termination),
+    Draw(Termination),
+}
+
+impl TictactoeResult {
+    /// The result and termination tags of an exported game.
+    fn tags(&self) -> (&'static str, &'static str) {
+        let (result, termination) = match self {
+            TictactoeResult::Win(TictactoeCell::X, termination) => ("1-0", termination),
+            TictactoeResult::Win(TictactoeCell::O, termination) => ("0-1", termination),
+            TictactoeResult::Draw(termination) => ("1/2-1/2", termination),
+        };
+        let termination = match termination {
+            Termination::Normal => "normal",
+            Termination::Resignation => "resignation",
+            Termination::TimeForfeit => "time forfeit",
+            Termination::Agreement => "agreement",
+        };
+        (result, termination)
+    }
+}
+
+/// Results of the games played between the same two players.
+#[derive(Clone, Default, Serialize, Deserialize)]
+struct TictactoeSeries {
+    wins: HashMap<UserId, u32>,
+    ties: u32,
+}
+
+impl TictactoeSeries {
+    fn played(&self) -> u32 {
+        self.wins.values().sum::<u32>() + self.ties
+    }
+
+    fn render(&self, player1: UserId, player2: UserId, locale: Locale) -> String {
+        let wins = |player| self.wins.get(&player).copied().unwrap_or_default();
+        let mut series = tr!(
+            locale,
+            "tictactoe-series",
+            player1 = player1.mention().to_string(),
+            wins1 = wins(player1),
+            wins2 = wins(player2),
+            player2 = player2.mention().to_string()
+        );
+        if self.ties > 0 {
+            series = format!(
+                "{} {}",
+                series,
+                tr!(locale, "tictactoe-ties", ties = self.ties)
+            );
+        }
+        series
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct TictactoeGame {
     player1: UserId,
     player2: Option<UserId>,
-    size: usize,
-    board: Vec<Option<TictactoeCell>>,
-    turn: TictactoeCell,
-    start_time: Option<Instant>,
+    /// The only player allowed to join, if `player1` challenged someone.
+    opponent: Option<UserId>,
+    board: TictactoeBoard,
+    start_time: Option<SystemTime>,
+    series: TictactoeSeries,
+    /// Players who have asked for a rematch after the game ended.
+    rematch: Vec<UserId>,
+    move_time: Duration,
+    /// When the player whose turn it is forfeits, while the game is running.
+    deadline: Option<SystemTime>,
+    /// When a challenge nobody has accepted is cancelled.
+    #[serde(default)]
+    challenge_expires: Option<SystemTime>,
+    /// The player waiting for an answer to their draw offer.
+    draw_offer: Option<UserId>,
+    /// Token of the last interaction to update the board, so it can be
+    /// edited from the control message of full size boards.
+    board_token: Option<String>,
+    history: Vec<MoveRecord<TictactoeAction>>,
+    result: Option<TictactoeResult>,
+    #[serde(default)]
+    locale: Locale,
 }
 
 impl TictactoeGame {
-    fn new(player: UserId, size: usize) -> TictactoeGame {
+    pub(crate) fn new(player: UserId, size: usize) -> TictactoeGame {
         TictactoeGame {
             player1: player,
             player2: None,
-            size,
-            board: repeat_with(|| None).take(size * size).collect::<Vec<_>>(),
-            turn: TictactoeCell::X,
+            opponent: None,
+            board: TictactoeBoard::new(size),
             start_time: None,
+            series: TictactoeSeries::default(),
+            rematch: Vec::new(),
+            move_time: DEFAULT_MOVE_TIME,
+            deadline: None,
+            challenge_expires: None,
+            draw_offer: None,
+            board_token: None,
+            history: Vec::new(),
+            result: None,
+            locale: Locale::default(),
         }
     }
 
-    fn start(&mut self, player: UserId) {
-        self.player2 = Some(player);
-        self.start_time = Some(Instant::now())
+    /// A new game between the same players, with whoever went second now
+    /// going first.
+    fn rematch(&self) -> TictactoeGame {
+        let mut game = TictactoeGame::new(self.player2.unwrap(), self.board.size());
+        game.board = self.board.restart();
+        game.move_time = self.move_time;
+        game.locale = self.locale;
+        game.start(self.player1);
+        game.series = self.series.clone();
+        game
     }
 
-    fn check_win(&self, cell_index: usize) -> Option<Vec<usize>> {
-        fn reverse(num: &usize, range: usize) -> usize {
-            (-(*num as isize) + range as isize - 1) as usize
+    /// `status` followed by the series score, once the players have finished
+    /// a game together, and the current player's deadline.
+    fn content(&self, status: String) -> String {
+        let mut content = status;
+        match self.player2 {
+            Some(player2) if self.series.played() > 0 => {
+                let series = self.series.render(self.player1, player2, self.locale);
+                content = format!("{}\n{}", content, series)
+            }
+            _ => {}
         }
+        if let Some(offer) = self.draw_offer {
+            let offered = tr!(
+                self.locale,
+                "tictactoe-draw-offered",
+                player = offer.mention().to_string()
+            );
+            content = format!("{}\n{}", content, offered)
+        }
+        if let Some(deadline) = self.deadline {
+            let timestamp = deadline.duration_since(UNIX_EPOCH).unwrap().as_secs();
+            let time = format!("<t:{}:R>", timestamp);
+            content = format!(
+                "{}\n{}",
+                content,
+                tr!(self.locale, "tictactoe-deadline", time = time)
+            )
+        }
+        content
+    }
 
-        let cell_x = cell_index % self.size;
-        let cell_y = cell_index / self.size;
-
-        // TODO: reduce code duplication
+    /// Gives the player whose turn it is `move_time` to move, returning the
+    /// new deadline.
+    fn start_clock(&mut self) -> SystemTime {
+        let deadline = SystemTime::now() + self.move_time;
+        self.deadline = Some(deadline);
+        deadline
+    }
 
-        // Rows
-        {
-            let mut indexes = (0..self.size).map(|x| cell_y * self.size + x);
-            let first = indexes.next().unwrap();
-            if indexes.clone().all(|i| self.board[i] == self.board[first]) {
-                let mut index_list = vec![first];
-                index_list.extend(indexes);
-                return Some(index_list);
+    /// Ends the game with `result`, adding it to the series.
+    fn end(&mut self, result: TictactoeResult) {
+        match result {
+            TictactoeResult::Win(cell, _) => {
+                *self
+                    .series
+                    .wins
+                    .entry(self.player(cell).unwrap())
+                    .or_default() += 1
             }
+            TictactoeResult::Draw(_) => self.series.ties += 1,
         }
-        // Columns
-        {
-            let mut indexes = (0..self.size).map(|y| y * self.size + cell_x);
-            let first = indexes.next().unwrap();
-            if indexes.clone().all(|i| self.board[i] == self.board[first]) {
-                let mut index_list = vec![first];
-                index_list.extend(indexes);
-                return Some(index_list);
-            }
+        self.result = Some(result);
+        self.board.end();
+        self.deadline = None;
+        self.draw_offer = None;
+    }
+
+    /// The board after the first `step` moves.
+    fn board_at(&self, step: usize) -> TictactoeBoard {
+        let mut board = self.board.restart();
+        for record in &self.history[..step] {
+            let player = board.turn();
+            apply_move(&mut board, player, record.action).unwrap();
         }
-        // Diagonal 1
-        {
-            if cell_x == cell_y {
-                let mut indexes = (0..self.size).map(|i| i * self.size + i);
-                let first = indexes.next().unwrap();
-                if indexes.clone().all(|i| self.board[i] == self.board[first]) {
-                    let mut index_list = vec![first];
-                    index_list.extend(indexes);
-                    return Some(index_list);
-                }
-            }
+        board
+    }
+
+    /// The game in PGN-like notation.
+    fn export(&self) -> String {
+        let start = self.start_time.unwrap();
+        let moves = timed_moves(start, &self.history, |action| match action {
+            TictactoeAction::Place(index) => cell_name(*index, self.board.size()),
+        });
+        let turns = moves
+            .chunks(2)
+            .map(|turn| turn.join(" "))
+            .collect::<Vec<_>>();
+        let (result, termination) = self.result.map_or(("*", "unterminated"), |r| r.tags());
+
+        let mut tags = vec![
+            ("Game", String::from("Tic-tac-toe")),
+            (
+                "Size",
+                format!("{}x{}", self.board.size(), self.board.size()),
+            ),
+            ("Start", unix_time(start).to_string()),
+            ("X", self.player1.to_string()),
+            (
+                "O",
+                self.player2.map_or_else(String::new, |p| p.to_string()),
+            ),
+            ("Result", String::from(result)),
+            ("Termination", String::from(termination)),
+        ];
+        if self.board.win_length() < self.board.size() {
+            tags.insert(2, ("WinLength", self.board.win_length().to_string()));
         }
-        // Diagonal 2
-        {
-            let rev_x = reverse(&cell_x, self.size);
-            if rev_x == cell_y {
-                let mut indexes = (0..self.size).map(|i| i * self.size + reverse(&i, self.size));
-                let first = indexes.next().unwrap();
-                if indexes.clone().all(|i| self.board[i] == self.board[first]) {
-                    let mut index_list = vec![first];
-                    index_list.extend(indexes);
-                    return Some(index_list);
-                }
-            }
+        export(&tags, &turns, result)
+    }
+
+    /// The board and clock, when they aren't the defaults.
+    fn settings(&self) -> Option<String> {
+        let board = &self.board;
+        if board.size() == 3 && board.win_length() == 3 && self.move_time == DEFAULT_MOVE_TIME {
+            return None;
         }
-        None
+        Some(tr!(
+            self.locale,
+            "tictactoe-settings",
+            size = board.size(),
+            length = board.win_length(),
+            seconds = self.move_time.as_secs()
+        ))
+    }
+
+    /// The numbers asked for by the lobby's "Custom game" form.
+    fn setup_fields(&self) -> [Field; 3] {
+        [
+            Field {
+                id: "size",
+                min: 2,
+                max: 5,
+                value: self.board.size(),
+            },
+            Field {
+                id: "win_length",
+                min: 2,
+                max: 5,
+                value: self.board.win_length(),
+            },
+            Field {
+                id: "move_time",
+                min: 10,
+                max: 600,
+                value: self.move_time.as_secs() as usize,
+            },
+        ]
+    }
+
+    fn turn_status(&self) -> String {
+        let player = self.player(self.board.turn()).unwrap();
+        tr!(
+            self.locale,
+            "tictactoe-turn",
+            player = player.mention().to_string()
+        )
     }
 
-    fn current_player(&self) -> Option<UserId> {
-        match self.turn {
+    fn start(&mut self, player: UserId) {
+        self.player2 = Some(player);
+        self.start_time = Some(SystemTime::now());
+        self.start_clock();
+    }
+
+    fn player(&self, cell: TictactoeCell) -> Option<UserId> {
+        match cell {
             TictactoeCell::X => Some(self.player1),
             TictactoeCell::O => self.player2,
         }
     }
 
-    fn render_board(&self, highlight_cells: Vec<usize>) -> CreateComponents {
-        let mut components = CreateComponents::default();
+    /// The mark played by `user`, if they are in this game.
+    fn cell_for(&self, user: UserId) -> Option<TictactoeCell> {
+        if user == self.player1 {
+            Some(TictactoeCell::X)
+        } else if Some(user) == self.player2 {
+            Some(TictactoeCell::O)
+        } else {
+            None
+        }
+    }
+}
 
-        let (highlight_style, game_over) = match highlight_cells.len() {
-            0..=1 => (ButtonStyle::Primary, false),
-            _ => (ButtonStyle::Success, true),
-        };
+/// The message inviting someone to play `game`, before it has started.
+fn render_lobby(game: &TictactoeGame) -> (String, Vec<CreateActionRow>) {
+    let locale = game.locale;
+    let player = game.player1.mention().to_string();
+    let (mut content, mut buttons) = match game.opponent {
+        Some(opponent) => (
+            tr!(
+                locale,
+                "tictactoe-challenged",
+                player = player,
+                opponent = opponent.mention().to_string()
+            ),
+            vec![
+                CreateButton::new("tictactoe-accept")
+                    .label(tr!(locale, "tictactoe-accept"))
+                    .style(ButtonStyle::Success),
+                CreateButton::new("tictactoe-decline")
+                    .label(tr!(locale, "tictactoe-decline"))
+                    .style(ButtonStyle::Danger),
+            ],
+        ),
+        None => (
+            tr!(locale, "tictactoe-started", player = player),
+            vec![CreateButton::new("tictactoe-join")
+                .label(tr!(locale, "tictactoe-join"))
+                .style(ButtonStyle::Success)],
+        ),
+    };
+    buttons.push(custom_button("tictactoe-custom", locale));
+    if let Some(settings) = game.settings() {
+        content = format!("{}\n{}", content, settings);
+    }
+    (content, vec![CreateActionRow::Buttons(buttons)])
+}
 
-        for y in 0..self.size {
-            components.create_action_row(|row| {
-                for x in 0..self.size {
-                    row.create_button(|button| {
-                        let index = y * self.size + x;
-                        if self.board[index].is_some() {
-                            button
-                                .emoji(ReactionType::Unicode(
-                                    self.board[index].as_ref().unwrap().render(),
-                                ))
-                                .disabled(true);
-                        } else {
-                            button.label(" ").disabled(game_over);
-                        }
-
-                        let style = if highlight_cells.iter().any(|&c| c == index) {
-                            highlight_style
-                        } else {
-                            ButtonStyle::Secondary
-                        };
-
-                        button
-                            .custom_id(format!("tictactoe-game-{}", index))
-                            .style(style)
-                    });
-                }
-                row
-            });
+fn render_board(board: &TictactoeBoard, highlight_cells: &[usize]) -> Vec<CreateActionRow> {
+    let highlight_style = if board.is_over() {
+        ButtonStyle::Success
+    } else {
+        ButtonStyle::Primary
+    };
+
+    (0..board.size())
+        .map(|y| {
+            let buttons = (0..board.size())
+                .map(|x| {
+                    let index = y * board.size() + x;
+                    let mut button = CreateButton::new(format!("tictactoe-game-{}", index));
+                    if let Some(cell) = board.cells()[index] {
+                        button = button
+                            .emoji(ReactionType::Unicode(render_cell(cell)))
+                            .disabled(true);
+                    } else {
+                        button = button.label(" ").disabled(board.is_over());
+                    }
+
+                    let style = if highlight_cells.contains(&index) {
+                        highlight_style
+                    } else {
+                        ButtonStyle::Secondary
+                    };
+
+                    button.style(style)
+                })
+                .collect();
+            CreateActionRow::Buttons(buttons)
+        })
+        .collect()
+}
+
+/// Resign and Offer Draw buttons for the game `game_id`, with Accept and
+/// Decline in place of Offer Draw while a draw is on offer.
+fn render_controls(game_id: GameId, draw_offered: bool, locale: Locale) -> CreateActionRow {
+    let mut buttons = vec![CreateButton::new(format!("tictactoe-resign-{}", game_id))
+        .label(tr!(locale, "tictactoe-resign"))
+        .style(ButtonStyle::Danger)];
+    if draw_offered {
+        buttons.push(
+            CreateButton::new(format!("tictactoe-drawaccept-{}", game_id))
+                .label(tr!(locale, "tictactoe-accept-draw"))
+                .style(ButtonStyle::Success),
+        );
+        buttons.push(
+            CreateButton::new(format!("tictactoe-drawdecline-{}", game_id))
+                .label(tr!(locale, "tictactoe-decline-draw"))
+                .style(ButtonStyle::Secondary),
+        );
+    } else {
+        buttons.push(
+            CreateButton::new(format!("tictactoe-draw-{}", game_id))
+                .label(tr!(locale, "tictactoe-offer-draw"))
+                .style(ButtonStyle::Secondary),
+        );
+    }
+    CreateActionRow::Buttons(buttons)
+}
+
+/// The board of a running game, with its controls below if there is room.
+fn render_running(
+    game: &TictactoeGame,
+    game_id: GameId,
+    highlight_cells: &[usize],
+) -> Vec<CreateActionRow> {
+    let mut components = render_board(&game.board, highlight_cells);
+    if game.board.size() < MAX_ROWS {
+        components.push(render_controls(
+            game_id,
+            game.draw_offer.is_some(),
+            game.locale,
+        ));
+    }
+    components
+}
+
+/// Full size boards have no room for controls, so they get their own message,
+/// given as its content and components.
+fn render_control_message(
+    game: &TictactoeGame,
+    game_id: GameId,
+) -> Option<(String, Vec<CreateActionRow>)> {
+    if game.board.size() < MAX_ROWS {
+        return None;
+    }
+
+    let components = vec![render_controls(game_id, false, game.locale)];
+    Some((tr!(game.locale, "tictactoe-controls"), components))
+}
+
+/// The board as emoji, for when there is no room to show it as buttons.
+fn render_text_board(board: &TictactoeBoard) -> String {
+    board
+        .cells()
+        .chunks(board.size())
+        .map(|row| {
+            row.iter()
+                .map(|cell| cell.map_or_else(|| String::from("\u{2B1C}"), render_cell))
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The finished board with Rematch and Replay buttons below it. Boards that already use
+/// every row are shown in the message content instead.
+fn render_game_over(
+    board: &TictactoeBoard,
+    highlight_cells: &[usize],
+    locale: Locale,
+) -> Vec<CreateActionRow> {
+    let mut components = if board.size() < MAX_ROWS {
+        render_board(board, highlight_cells)
+    } else {
+        Vec::new()
+    };
+
+    components.push(CreateActionRow::Buttons(vec![
+        CreateButton::new("tictactoe-rematch")
+            .label(tr!(locale, "tictactoe-rematch"))
+            .style(ButtonStyle::Primary),
+        CreateButton::new("tictactoe-replay")
+            .label(tr!(locale, "replay"))
+            .style(ButtonStyle::Secondary),
+    ]));
+
+    components
+}
+
+/// The message for a finished game: `status` followed by the final board.
+fn render_finished(
+    game: &TictactoeGame,
+    status: String,
+    highlight_cells: &[usize],
+) -> (String, Vec<CreateActionRow>) {
+    let mut content = game.content(status);
+    if game.board.size() >= MAX_ROWS {
+        content = format!("{}\n\n{}", content, render_text_board(&game.board));
+    }
+    let components = render_game_over(&game.board, highlight_cells, game.locale);
+    (content, components)
+}
+
+/// The answer to a button press. It is worked out while the game is locked,
+/// then sent once the lock has been released.
+enum Reply {
+    /// A message only the player who pressed the button can see.
+    Private(String),
+    /// A new version of the message the button is on.
+    Update(Update),
+    /// A replay only the player who pressed the button can see, with the
+    /// game attached.
+    Replay(String, CreateActionRow, CreateAttachment),
+    /// A form for the player who pressed the button to fill in.
+    Modal(CreateModal),
+}
+
+#[derive(Default)]
+struct Update {
+    content: String,
+    /// Replaces the message's components, if set.
+    components: Option<Vec<CreateActionRow>>,
+    /// Whether the players mentioned in the content are pinged.
+    ping: bool,
+    /// Content and controls to send in a follow-up, for boards without room
+    /// for them.
+    controls: Option<(String, Vec<CreateActionRow>)>,
+    /// New content and components for the board, given the token of the
+    /// interaction that last showed it, when the button is on another message.
+    board: Option<(String, String, Vec<CreateActionRow>)>,
+    /// Starts the clock of the player whose turn it is.
+    deadline: Option<SystemTime>,
+    /// Sends the update as a new message for the rematch stored under this
+    /// id, leaving the finished game's message as it is.
+    rematch: Option<GameId>,
+}
+
+impl Reply {
+    fn private(content: String) -> Result<Reply, Error> {
+        Ok(Reply::Private(content))
+    }
+
+    /// Clears the message of a game that is no longer stored.
+    fn expired(locale: Locale) -> Reply {
+        Reply::Update(Update {
+            content: tr!(locale, "tictactoe-expired"),
+            components: Some(Vec::new()),
+            ping: true,
+            ..Update::default()
+        })
+    }
+
+    /// Shows a game that has just started, giving `token` the board.
+    fn started(game: &mut TictactoeGame, game_id: GameId, status: String, token: &str) -> Reply {
+        Reply::Update(Update::started(game, game_id, status, token))
+    }
+}
+
+impl Update {
+    fn started(game: &mut TictactoeGame, game_id: GameId, status: String, token: &str) -> Update {
+        game.board_token = Some(token.to_string());
+        Update {
+            content: game.content(status),
+            components: Some(render_running(game, game_id, &[])),
+            ping: true,
+            controls: render_control_message(game, game_id),
+            deadline: game.deadline,
+            ..Update::default()
         }
+    }
+}
+
+/// Shows the state of `game` after one of its controls was pressed. Boards
+/// with room for the controls are updated in place; otherwise the control
+/// message shows `note` and the board is edited separately.
+fn update_controls(
+    component: &ComponentInteraction,
+    game: &mut TictactoeGame,
+    game_id: GameId,
+    status: String,
+    note: String,
+) -> Reply {
+    let (content, components) = if game.board.is_over() {
+        render_finished(game, status, &[])
+    } else {
+        (game.content(status), render_running(game, game_id, &[]))
+    };
+
+    if game.board.size() < MAX_ROWS {
+        game.board_token = Some(component.token.clone());
+        return Reply::Update(Update {
+            content,
+            components: Some(components),
+            ..Update::default()
+        });
+    }
 
-        components
+    let mut controls = Vec::new();
+    if !game.board.is_over() {
+        let draw_offered = game.draw_offer.is_some();
+        controls.push(render_controls(game_id, draw_offered, game.locale));
     }
+    Reply::Update(Update {
+        content: note,
+        components: Some(controls),
+        board: game
+            .board_token
+            .clone()
+            .map(|token| (token, content, components)),
+        ..Update::default()
+    })
 }
 
-pub struct TictactoeGames;
+async fn send_reply(
+    games: &TictactoeGames,
+    responder: &impl Responder,
+    game_id: GameId,
+    reply: Reply,
+) -> Result<(), Error> {
+    let update = match reply {
+        Reply::Private(content) => {
+            return responder
+                .create_interaction_response(CreateInteractionResponse::Message(
+                    CreateInteractionResponseMessage::new()
+                        .content(content)
+                        .ephemeral(true),
+                ))
+                .await
+        }
+        Reply::Replay(content, steps, file) => {
+            return responder
+                .create_interaction_response_with_files(
+                    CreateInteractionResponse::Message(
+                        CreateInteractionResponseMessage::new()
+                            .content(content)
+                            .components(vec![steps])
+                            .ephemeral(true),
+                    ),
+                    vec![file],
+                )
+                .await
+        }
+        Reply::Modal(modal) => {
+            return responder
+                .create_interaction_response(CreateInteractionResponse::Modal(modal))
+                .await
+        }
+        Reply::Update(update) => update,
+    };
 
-impl TypeMapKey for TictactoeGames {
-    type Value = HashMap<InteractionId, TictactoeGame>;
+    let mut message = CreateInteractionResponseMessage::new().content(update.content);
+    if !update.ping {
+        message = message.allowed_mentions(CreateAllowedMentions::new().empty_users());
+    }
+    if let Some(components) = update.components {
+        message = message.components(components);
+    }
+    let response = match update.rematch {
+        Some(_) => CreateInteractionResponse::Message(message),
+        None => CreateInteractionResponse::UpdateMessage(message),
+    };
+    responder.create_interaction_response(response).await?;
+
+    if let Some((token, content, components)) = update.board {
+        responder
+            .edit_interaction_response(
+                &token,
+                EditInteractionResponse::new()
+                    .content(content)
+                    .allowed_mentions(CreateAllowedMentions::new().empty_users())
+                    .components(components),
+            )
+            .await?;
+    }
+    if let Some((content, controls)) = update.controls {
+        responder
+            .create_followup_message(
+                CreateInteractionResponseFollowup::new()
+                    .content(content)
+                    .components(controls),
+            )
+            .await?;
+    }
+    if let Some(deadline) = update.deadline {
+        let game_id = update.rematch.unwrap_or(game_id);
+        run_clock(games.clone(), responder.clone(), game_id, deadline);
+    }
+    Ok(())
+}
+
+impl Game for TictactoeGame {
+    const NAME: &'static str = "tictactoe";
+
+    fn is_over(&self) -> bool {
+        self.board.is_over()
+    }
+
+    fn has_player(&self, user: UserId) -> bool {
+        self.player1 == user || self.player2 == Some(user)
+    }
+
+    fn token(&self) -> Option<&str> {
+        self.board_token.as_deref()
+    }
+
+    fn locale(&self) -> Locale {
+        self.locale
+    }
 }
 
+pub type TictactoeGames = GameStore<TictactoeGame>;
+
 pub async fn tictactoe(
-    ctx: &Context,
-    command: &ApplicationCommandInteraction,
+    data: &RwLock<TypeMap>,
+    responder: &impl Responder,
+    command: &CommandInteraction,
 ) -> Result<(), Error> {
-    let mut game_data = ctx.data.write().await;
-    let game_list = game_data.get_mut::<TictactoeGames>().unwrap();
-
     let mut size = 3;
-    if let Some(option) = command.data.options.get(0) {
-        if let Some(ApplicationCommandInteractionDataOptionValue::Integer(osize)) = &option.resolved
-        {
-            size = *osize as usize;
+    let mut opponent = None;
+    let mut move_time = DEFAULT_MOVE_TIME;
+    for option in command.data.options() {
+        match (option.name, option.value) {
+            ("size", ResolvedValue::Integer(osize)) => size = osize as usize,
+            ("opponent", ResolvedValue::User(user, _)) => opponent = Some(user.clone()),
+            ("move_time", ResolvedValue::Integer(secs)) => {
+                move_time = Duration::from_secs(secs as u64)
+            }
+            _ => {}
         }
     }
 
-    game_list.insert(command.id, TictactoeGame::new(command.user.id, size));
+    let locale = Locale::of_guild(command.guild_locale.as_deref(), &command.locale);
+    let user_locale = Locale::of_user(&command.locale);
 
-    let name = command.user.mention();
-
-    command
-        .create_interaction_response(&ctx.http, |response| {
-            response.interaction_response_data(|data| {
-                data.content(format!(
-                    "{} has started a game of tic-tac-toe! Who would like to play?",
-                    name
+    let opponent = match opponent {
+        Some(opponent) if opponent.id == command.user.id => {
+            return responder
+                .create_interaction_response(CreateInteractionResponse::Message(
+                    CreateInteractionResponseMessage::new()
+                        .content(tr!(user_locale, "tictactoe-challenge-self"))
+                        .ephemeral(true),
                 ))
-                .allowed_mentions(|mentions| mentions.empty_users())
-                .components(|components| {
-                    components.create_action_row(|row| {
-                        row.create_button(|button| {
-                            button
-                                .label("Join")
-                                .custom_id("tictactoe-join")
-                                .style(ButtonStyle::Success)
-                        })
-                    })
-                })
-            })
-        })
+                .await;
+        }
+        Some(opponent) if opponent.bot => {
+            return responder
+                .create_interaction_response(CreateInteractionResponse::Message(
+                    CreateInteractionResponseMessage::new()
+                        .content(tr!(user_locale, "tictactoe-challenge-bot"))
+                        .ephemeral(true),
+                ))
+                .await;
+        }
+        opponent => opponent.map(|opponent| opponent.id),
+    };
+
+    let mut game = TictactoeGame::new(command.user.id, size);
+    game.opponent = opponent;
+    game.challenge_expires = opponent.map(|_| SystemTime::now() + CHALLENGE_TIMEOUT);
+    game.move_time = move_time;
+    game.board_token = Some(command.token.clone());
+    game.locale = locale;
+    let game_id = GameId::from(command.id);
+    game_id.record();
+    let games = TictactoeGames::of(data).await;
+    let (content, components) = render_lobby(&game);
+    let challenge_expires = game.challenge_expires;
+    games.insert(game_id, game);
+
+    // Only a challenged player is pinged
+    let mentions = match opponent {
+        Some(opponent) => CreateAllowedMentions::new().users([opponent]),
+        None => CreateAllowedMentions::new().empty_users(),
+    };
+    responder
+        .create_interaction_response(CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new()
+                .content(content)
+                .allowed_mentions(mentions)
+                .components(components),
+        ))
         .await?;
+
+    if let Some(expires) = challenge_expires {
+        expire_challenge(games, responder.clone(), game_id, expires);
+    }
     Ok(())
 }
 
+/// Restarts the clocks and challenge timeouts of games loaded from a save,
+/// since the tasks running them were lost with the restart. `responder`
+/// edits the message of a game given its id and the token that last showed
+/// it.
+pub fn resume_timers<R: Responder>(games: &TictactoeGames, responder: impl Fn(GameId, &str) -> R) {
+    for id in games.ids() {
+        let timers = games.with(id, |game| {
+            let token = game.board_token.clone()?;
+            let deadline = game.deadline.filter(|_| !game.board.is_over());
+            let challenge = match (game.opponent, game.player2) {
+                (Some(_), None) => Some(
+                    game.challenge_expires
+                        .unwrap_or_else(|| SystemTime::now() + CHALLENGE_TIMEOUT),
+                ),
+                _ => None,
+            };
+            Some((token, deadline, challenge))
+        });
+        let (token, deadline, challenge) = match timers {
+            Some(Some(timers)) => timers,
+            _ => continue,
+        };
+
+        if let Some(deadline) = deadline {
+            run_clock(games.clone(), responder(id, &token), id, deadline);
+        }
+        if let Some(expires) = challenge {
+            expire_challenge(games.clone(), responder(id, &token), id, expires);
+        }
+    }
+}
+
+/// Forfeits the game `id` for the player whose turn it is if they haven't
+/// moved by `deadline`, editing the message `responder` last updated.
+fn run_clock(games: TictactoeGames, responder: impl Responder, id: GameId, deadline: SystemTime) {
+    let task = async move {
+        let wait = deadline
+            .duration_since(SystemTime::now())
+            .unwrap_or_default();
+        tokio::time::sleep(wait).await;
+
+        let forfeit = games.with(id, |game| {
+            if game.deadline != Some(deadline) {
+                return None;
+            }
+
+            let idle = game.player(game.board.turn()).unwrap();
+            let winner = game.player(game.board.turn().other()).unwrap();
+            game.end(TictactoeResult::Win(
+                game.board.turn().other(),
+                Termination::TimeForfeit,
+            ));
+
+            let status = tr!(
+                game.locale,
+                "tictactoe-timeout",
+                player = idle.mention().to_string(),
+                winner = winner.mention().to_string()
+            );
+            Some(render_finished(game, status, &[]))
+        });
+        let (content, components) = match forfeit {
+            Some(Some(forfeit)) => forfeit,
+            _ => return,
+        };
+
+        let result = responder
+            .edit_original_interaction_response(
+                EditInteractionResponse::new()
+                    .content(content)
+                    .allowed_mentions(CreateAllowedMentions::new().empty_users())
+                    .components(components),
+            )
+            .await;
+        if let Err(err) = result {
+            error!("Failed to forfeit tic-tac-toe game: {}", err);
+        }
+    };
+    tokio::spawn(task.in_current_span());
+}
+
+/// Cancels the challenge `id` if it hasn't been accepted by `expires`.
+fn expire_challenge(
+    games: TictactoeGames,
+    responder: impl Responder,
+    id: GameId,
+    expires: SystemTime,
+) {
+    let task = async move {
+        let wait = expires
+            .duration_since(SystemTime::now())
+            .unwrap_or_default();
+        tokio::time::sleep(wait).await;
+
+        let expired = games.with(id, |game| {
+            if game.player2.is_some() {
+                return None;
+            }
+
+            games.remove(id);
+            Some(tr!(
+                game.locale,
+                "tictactoe-challenge-expired",
+                player = game.player1.mention().to_string(),
+                opponent = game.opponent.unwrap().mention().to_string()
+            ))
+        });
+        let content = match expired {
+            Some(Some(content)) => content,
+            _ => return,
+        };
+
+        let result = responder
+            .edit_original_interaction_response(
+                EditInteractionResponse::new()
+                    .content(content)
+                    .allowed_mentions(CreateAllowedMentions::new().empty_users())
+                    .components(Vec::new()),
+            )
+            .await;
+        if let Err(err) = result {
+            error!("Failed to expire tic-tac-toe challenge: {}", err);
+        }
+    };
+    tokio::spawn(task.in_current_span());
+}
+
+/// The game on the message `component` is on, named by the command or the
+/// rematch button press that sent it.
+fn message_game(component: &ComponentInteraction) -> Result<GameId, Error> {
+    match component.message.interaction_metadata.as_deref() {
+        Some(MessageInteractionMetadata::Command(command)) => Ok(command.id.into()),
+        Some(MessageInteractionMetadata::Component(press)) => Ok(press.id.into()),
+        _ => Err("Couldnt find original interaction".into()),
+    }
+}
+
+/// The command that created the message `component` is on.
+fn original_command(
+    component: &ComponentInteraction,
+) -> Result<&MessageCommandInteractionMetadata, Error> {
+    match component.message.interaction_metadata.as_deref() {
+        Some(MessageInteractionMetadata::Command(command)) => Ok(command),
+        _ => Err("Couldnt find original interaction".into()),
+    }
+}
+
 pub async fn tictactoe_button(
-    ctx: &Context,
-    component: &MessageComponentInteraction,
+    data: &RwLock<TypeMap>,
+    responder: &impl Responder,
+    component: &ComponentInteraction,
 ) -> Result<(), Error> {
     let component_id = component.data.custom_id.clone();
     let mut split = component_id.split('-');
     split.next();
+    let kind = split.next().ok_or("Missing button type")?;
 
-    let mut game_data = ctx.data.write().await;
-    let game_list = game_data.get_mut::<TictactoeGames>().unwrap();
-    match game_list.get_mut(&component.message.interaction.as_ref().unwrap().id) {
-        Some(game) => match split.next().ok_or("Missing button type")? {
-            "join" => {
-                if component.user.id
-                    != component
-                        .message
-                        .interaction
-                        .as_ref()
-                        .ok_or("Couldnt find original interaction")?
-                        .user
-                        .id
-                {
-                    if game.player2.is_none() {
-                        game.start(component.user.id);
-                        component
-                            .create_interaction_response(&ctx.http, |response| {
-                                response
-                                    .kind(InteractionResponseType::UpdateMessage)
-                                    .interaction_response_data(|data| {
-                                        data.content(format!("{}'s turn!", game.player1.mention()))
-                                            .set_components(game.render_board(Vec::new()))
-                                    })
-                            })
-                            .await?
-                    } else {
-                        component.create_interaction_response(&ctx.http, |response| {
-                                response.interaction_response_data(|data| {
-                                    data
-                                        .content("Someone already joined this game! You can create your own with `/tictactoe`.")
-                                        .ephemeral(true)
-                                })
-                            })
-                            .await?
-                    }
-                } else {
-                    component
-                        .create_interaction_response(&ctx.http, |response| {
-                            response.interaction_response_data(|data| {
-                                data.content(
-                                    "You can't join your own game! Find someone else to play with.",
-                                )
-                                .ephemeral(true)
-                            })
-                        })
-                        .await?
+    // Controls can be on a follow-up message, so they name their game
+    let game_id: GameId = match kind {
+        "resign" | "draw" | "drawaccept" | "drawdecline" | "step" => split
+            .next()
+            .ok_or("Missing game id in component custom id")?
+            .parse()?,
+        _ => message_game(component)?,
+    };
+    game_id.record();
+    let games = TictactoeGames::of(data).await;
+    let reply = games.with(game_id, |game| {
+        answer_button(&games, game, game_id, kind, split.next(), component)
+    });
+    let reply = match reply {
+        Some(reply) => reply?,
+        None => Reply::expired(Locale::of_guild(
+            component.guild_locale.as_deref(),
+            &component.locale,
+        )),
+    };
+    send_reply(&games, responder, game_id, reply).await
+}
+
+/// Applies the lobby's "Custom game" form to the game it was opened from.
+pub async fn tictactoe_setup(
+    data: &RwLock<TypeMap>,
+    responder: &impl Responder,
+    modal: &ModalInteraction,
+) -> Result<(), Error> {
+    let game_id: GameId = modal
+        .data
+        .custom_id
+        .strip_prefix("tictactoe-setup-")
+        .ok_or("Missing game id in modal custom id")?
+        .parse()?;
+    game_id.record();
+
+    let user_locale = Locale::of_user(&modal.locale);
+    let games = TictactoeGames::of(data).await;
+    let reply = games.with(game_id, |game| {
+        if modal.user.id != game.player1 {
+            return Reply::private(tr!(user_locale, "tictactoe-custom-host"));
+        }
+        if game.player2.is_some() {
+            return Reply::private(tr!(user_locale, "tictactoe-already-joined"));
+        }
+        let [size, win_length, move_time] = match read(modal, &game.setup_fields(), user_locale) {
+            Ok(values) => values,
+            Err(errors) => return Reply::private(errors),
+        };
+        if win_length > size {
+            return Reply::private(tr!(
+                user_locale,
+                "tictactoe-win-length-too-long",
+                size = size
+            ));
+        }
+
+        game.board = TictactoeBoard::with_win_length(size, win_length);
+        game.move_time = Duration::from_secs(move_time as u64);
+        let (content, components) = render_lobby(game);
+        Ok(Reply::Update(Update {
+            content,
+            components: Some(components),
+            ..Update::default()
+        }))
+    });
+    let reply = match reply {
+        Some(reply) => reply?,
+        None => Reply::expired(Locale::of_guild(
+            modal.guild_locale.as_deref(),
+            &modal.locale,
+        )),
+    };
+    send_reply(&games, responder, game_id, reply).await
+}
+
+/// Works out the answer to a `kind` button press on `game`. `argument` is
+/// the rest of the button's custom id, if any.
+fn answer_button(
+    games: &TictactoeGames,
+    game: &mut TictactoeGame,
+    game_id: GameId,
+    kind: &str,
+    argument: Option<&str>,
+    component: &ComponentInteraction,
+) -> Result<Reply, Error> {
+    let user_locale = Locale::of_user(&component.locale);
+    let name = component.user.mention().to_string();
+    match kind {
+        "accept" => {
+            if Some(component.user.id) != game.opponent {
+                return Reply::private(tr!(user_locale, "tictactoe-not-for-you"));
+            }
+            if game.player2.is_some() {
+                return Reply::private(tr!(user_locale, "tictactoe-already-joined"));
+            }
+
+            game.start(component.user.id);
+            let player = game.player1.mention().to_string();
+            let status = tr!(game.locale, "tictactoe-first-turn", player = player);
+            Ok(Reply::started(game, game_id, status, &component.token))
+        }
+        "decline" => {
+            let content = if Some(component.user.id) == game.opponent {
+                tr!(
+                    game.locale,
+                    "tictactoe-challenge-declined",
+                    opponent = name,
+                    player = game.player1.mention().to_string()
+                )
+            } else if component.user.id == game.player1 {
+                tr!(game.locale, "tictactoe-challenge-cancelled", player = name)
+            } else {
+                return Reply::private(tr!(user_locale, "tictactoe-not-for-you"));
+            };
+
+            games.remove(game_id);
+            Ok(Reply::Update(Update {
+                content,
+                components: Some(Vec::new()),
+                ..Update::default()
+            }))
+        }
+        "join" => {
+            if component.user.id == original_command(component)?.user.id {
+                return Reply::private(tr!(user_locale, "tictactoe-join-own"));
+            }
+            if game.player2.is_some() {
+                return Reply::private(tr!(user_locale, "tictactoe-already-joined"));
+            }
+
+            game.start(component.user.id);
+            let player = game.player1.mention().to_string();
+            let status = tr!(game.locale, "tictactoe-first-turn", player = player);
+            Ok(Reply::started(game, game_id, status, &component.token))
+        }
+        "custom" => {
+            if component.user.id != game.player1 {
+                return Reply::private(tr!(user_locale, "tictactoe-custom-host"));
+            }
+            if game.player2.is_some() {
+                return Reply::private(tr!(user_locale, "tictactoe-already-joined"));
+            }
+
+            let custom_id = format!("tictactoe-setup-{}", game_id);
+            Ok(Reply::Modal(modal(
+                custom_id,
+                &game.setup_fields(),
+                user_locale,
+            )))
+        }
+        "game" => {
+            let index = argument
+                .ok_or("Missing cell index in component custom id")?
+                .parse::<usize>()?;
+
+            let cell = match game.cell_for(component.user.id) {
+                Some(cell) => cell,
+                None => return Reply::private(tr!(user_locale, "tictactoe-not-yours")),
+            };
+
+            let mut board = game.board.clone();
+            let outcome = match apply_move(&mut board, cell, TictactoeAction::Place(index)) {
+                Ok(outcome) => outcome,
+                Err(MoveError::NotYourTurn) => {
+                    return Reply::private(tr!(user_locale, "tictactoe-not-your-turn"))
+                }
+                // The other player got there first
+                Err(MoveError::Occupied) => {
+                    return Reply::private(tr!(user_locale, "tictactoe-taken"))
+                }
+                Err(MoveError::GameOver) => {
+                    return Reply::private(tr!(user_locale, "tictactoe-over"))
+                }
+                Err(err) => return Err(err.into()),
+            };
+
+            game.board = board;
+            game.history
+                .push(MoveRecord::now(TictactoeAction::Place(index)));
+
+            let (status, highlight_cells) = match &outcome {
+                TictactoeOutcome::Win(line) => {
+                    game.end(TictactoeResult::Win(cell, Termination::Normal));
+                    let status = tr!(game.locale, "tictactoe-won", player = name);
+                    (status, line.clone())
                 }
+                TictactoeOutcome::Tie => {
+                    game.end(TictactoeResult::Draw(Termination::Normal));
+                    (tr!(game.locale, "tictactoe-tie"), vec![index])
+                }
+                TictactoeOutcome::Continue => (game.turn_status(), vec![index]),
+            };
+
+            let (content, components) = if game.board.is_over() {
+                render_finished(game, status, &highlight_cells)
+            } else {
+                game.start_clock();
+                (
+                    game.content(status),
+                    render_running(game, game_id, &highlight_cells),
+                )
+            };
+
+            game.board_token = Some(component.token.clone());
+            Ok(Reply::Update(Update {
+                content,
+                components: Some(components),
+                // Only ping the player whose turn it is
+                ping: !game.board.is_over(),
+                deadline: game.deadline,
+                ..Update::default()
+            }))
+        }
+        "rematch" => {
+            if game.cell_for(component.user.id).is_none() {
+                return Reply::private(tr!(user_locale, "tictactoe-not-yours"));
             }
-            "game" => {
-                let index = split
-                    .next()
-                    .ok_or("Missing cell index in component custom id")?
-                    .parse::<usize>()?;
-
-                if component.user.id == game.player1 || component.user.id == game.player2.unwrap() {
-                    if component.user.id == game.current_player().unwrap() {
-                        let mut temp_game = game.clone();
-
-                        if temp_game.board[index].is_none() {
-                            temp_game.board[index] = Some(temp_game.turn);
-                        } else {
-                            return Err(Error::from(
-                                "Filled cell selected which should be disabled",
-                            ));
-                        }
-
-                        let highlight_tiles =
-                            temp_game.check_win(index).unwrap_or_else(|| vec![index]);
-
-                        let won = highlight_tiles.len() > 1;
-                        let tie = temp_game.board.iter().all(|&c| c.is_some());
-
-                        temp_game.turn = match temp_game.turn {
-                            TictactoeCell::X => TictactoeCell::O,
-                            TictactoeCell::O => TictactoeCell::X,
-                        };
-
-                        if won {
-                            component
-                                .create_interaction_response(&ctx.http, |response| {
-                                    response
-                                        .kind(InteractionResponseType::UpdateMessage)
-                                        .interaction_response_data(|data| {
-                                            data.content(format!(
-                                                "{} won!",
-                                                game.current_player().unwrap().mention()
-                                            ))
-                                            .allowed_mentions(|mentions| mentions.empty_users())
-                                            .set_components(temp_game.render_board(highlight_tiles))
-                                        })
-                                })
-                                .await?;
-
-                            game_list.remove(&component.message.interaction.as_ref().unwrap().id);
-                        } else if tie {
-                            component
-                                .create_interaction_response(&ctx.http, |response| {
-                                    response
-                                        .kind(InteractionResponseType::UpdateMessage)
-                                        .interaction_response_data(|data| {
-                                            data.content("It's a tie!").set_components(
-                                                temp_game.render_board(highlight_tiles),
-                                            )
-                                        })
-                                })
-                                .await?
-                        } else {
-                            component
-                                .create_interaction_response(&ctx.http, |response| {
-                                    response
-                                        .kind(InteractionResponseType::UpdateMessage)
-                                        .interaction_response_data(|data| {
-                                            data.content(format!(
-                                                "{}'s turn.",
-                                                temp_game.current_player().unwrap().mention()
-                                            ))
-                                            .set_components(temp_game.render_board(highlight_tiles))
-                                        })
-                                })
-                                .await?;
-
-                            *game = temp_game;
-                        }
-                    } else {
-                        component.create_interaction_response(&ctx.http, |response| {
-                                response.interaction_response_data(|data| {
-                                    data
-                                        .content("It's not your turn! Wait for the other player to make a move.")
-                                        .ephemeral(true)
-                                })
-                            })
-                            .await?
-                    }
+            // Both players asking means the rematch is already being played
+            if game.rematch.len() > 1 {
+                return Reply::private(tr!(user_locale, "tictactoe-rematch-underway"));
+            }
+            if game.rematch.contains(&component.user.id) {
+                return Reply::private(tr!(user_locale, "tictactoe-rematch-again"));
+            }
+
+            if game.rematch.is_empty() {
+                game.rematch.push(component.user.id);
+                Ok(Reply::Update(Update {
+                    content: format!(
+                        "{}\n{}",
+                        component.message.content,
+                        tr!(game.locale, "tictactoe-rematch-asked", player = name)
+                    ),
+                    ..Update::default()
+                }))
+            } else {
+                // The rematch gets a message of its own, so this game can
+                // still be replayed until it expires
+                game.rematch.push(component.user.id);
+                let mut rematch = game.rematch();
+                let rematch_id = GameId::from(component.id);
+                let player = rematch.player1.mention().to_string();
+                let status = tr!(game.locale, "tictactoe-rematch-started", player = player);
+                let update = Update {
+                    rematch: Some(rematch_id),
+                    ..Update::started(&mut rematch, rematch_id, status, &component.token)
+                };
+                games.insert(rematch_id, rematch);
+                Ok(Reply::Update(update))
+            }
+        }
+        "replay" | "step" => {
+            let step = match argument {
+                Some(step) => step.parse::<usize>()?.min(game.history.len()),
+                None if kind == "step" => return Err("Missing step in component custom id".into()),
+                None => 0,
+            };
+
+            let mut content = step_heading(step, game.history.len(), user_locale);
+            if let Some(record) = step.checked_sub(1).map(|i| &game.history[i]) {
+                let TictactoeAction::Place(index) = record.action;
+                let cell = if step % 2 == 1 {
+                    TictactoeCell::X
                 } else {
-                    component
-                        .create_interaction_response(&ctx.http, |response| {
-                            response.interaction_response_data(|data| {
-                                data.content(
-                                    "That's not your game! Create your own with `/tictactoe`.",
-                                )
-                                .ephemeral(true)
-                            })
-                        })
-                        .await?
+                    TictactoeCell::O
+                };
+                content = format!(
+                    "{}: {} {}",
+                    content,
+                    render_cell(cell),
+                    cell_name(index, game.board.size())
+                );
+            }
+            content = format!("{}\n\n{}", content, render_text_board(&game.board_at(step)));
+            let steps = render_steps(
+                &format!("tictactoe-step-{}", game_id),
+                step,
+                game.history.len(),
+                user_locale,
+            );
+
+            if kind == "replay" {
+                let file = export_file(format!("tictactoe-{}.txt", game_id), game.export());
+                Ok(Reply::Replay(content, steps, file))
+            } else {
+                Ok(Reply::Update(Update {
+                    content,
+                    components: Some(vec![steps]),
+                    ping: true,
+                    ..Update::default()
+                }))
+            }
+        }
+        "resign" | "draw" | "drawaccept" | "drawdecline" => {
+            let cell = match game.cell_for(component.user.id) {
+                Some(cell) => cell,
+                None => return Reply::private(tr!(user_locale, "tictactoe-not-yours")),
+            };
+            let answer = match (kind, game.draw_offer) {
+                _ if game.board.is_over() => Err("tictactoe-over"),
+                ("resign", _) => {
+                    let winner = game.player(cell.other()).unwrap();
+                    game.end(TictactoeResult::Win(cell.other(), Termination::Resignation));
+                    let status = tr!(
+                        game.locale,
+                        "tictactoe-resigned",
+                        player = name,
+                        winner = winner.mention().to_string()
+                    );
+                    Ok((status.clone(), status))
+                }
+                ("draw", None) => {
+                    game.draw_offer = Some(component.user.id);
+                    let note = tr!(game.locale, "tictactoe-draw-offered", player = name);
+                    Ok((game.turn_status(), note))
+                }
+                ("draw", Some(_)) => Err("tictactoe-draw-pending"),
+                ("drawaccept", Some(offer)) if offer != component.user.id => {
+                    game.end(TictactoeResult::Draw(Termination::Agreement));
+                    let status = tr!(game.locale, "tictactoe-draw-agreed");
+                    Ok((status.clone(), status))
                 }
+                ("drawaccept", Some(_)) => Err("tictactoe-draw-own"),
+                ("drawdecline", Some(offer)) => {
+                    game.draw_offer = None;
+                    let note = if offer == component.user.id {
+                        tr!(game.locale, "tictactoe-draw-withdrawn", player = name)
+                    } else {
+                        tr!(game.locale, "tictactoe-draw-declined", player = name)
+                    };
+                    Ok((game.turn_status(), note))
+                }
+                _ => Err("tictactoe-no-draw"),
+            };
+
+            match answer {
+                Ok((status, note)) => Ok(update_controls(component, game, game_id, status, note)),
+                Err(message) => Reply::private(tr!(user_locale, message)),
             }
+        }
 
-            _ => return Err(Error::from("Unknown button type")),
-        },
-        None => {
-            component
-                .create_interaction_response(&ctx.http, |response| {
-                    response
-                        .kind(InteractionResponseType::UpdateMessage)
-                        .interaction_response_data(|data| {
-                            data.content(
-                                "This game has expired, start a new one with `/tictactoe`.",
-                            )
-                            .set_components(CreateComponents::default())
-                        })
-                })
-                .await?;
+        _ => Err(Error::from("Unknown button type")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use serenity::json::{json, Value};
+
+    use super::*;
+    use crate::commands::responder::testing::*;
+
+    const GAME: u64 = 1;
+    const PLAYER1: UserId = UserId::new(10);
+    const PLAYER2: UserId = UserId::new(20);
+
+    fn games(game: Option<TictactoeGame>) -> Arc<RwLock<TypeMap>> {
+        let games = GameStore::default();
+        if let Some(game) = game {
+            games.insert(GameId(GAME), game);
         }
-    };
-    Ok(())
+        let mut data = TypeMap::new();
+        data.insert::<TictactoeGames>(games);
+        Arc::new(RwLock::new(data))
+    }
+
+    fn started_game() -> TictactoeGame {
+        let mut game = TictactoeGame::new(PLAYER1, 3);
+        game.start(PLAYER2);
+        game
+    }
+
+    async fn press_with(
+        data: &Arc<RwLock<TypeMap>>,
+        responder: &RecordingResponder,
+        custom_id: &str,
+        user: UserId,
+    ) -> Value {
+        let component = component_interaction(custom_id, user, GAME, PLAYER1);
+        tictactoe_button(data, responder, &component).await.unwrap();
+        responder.last()
+    }
+
+    async fn press(data: &Arc<RwLock<TypeMap>>, custom_id: &str, user: UserId) -> Value {
+        press_with(data, &RecordingResponder::default(), custom_id, user).await
+    }
+
+    /// A copy of the game as it is now.
+    async fn stored_game(data: &Arc<RwLock<TypeMap>>) -> TictactoeGame {
+        TictactoeGames::of(data)
+            .await
+            .with(GameId(GAME), |game| game.clone())
+            .unwrap()
+    }
+
+    /// The line showing the current player's deadline.
+    async fn deadline(data: &Arc<RwLock<TypeMap>>) -> String {
+        let game = stored_game(data).await;
+        let deadline = game.deadline.unwrap().duration_since(UNIX_EPOCH).unwrap();
+        format!("\nMake your move <t:{}:R>.", deadline.as_secs())
+    }
+
+    /// Plays `cells` in order, alternating between the players.
+    async fn play(data: &Arc<RwLock<TypeMap>>, cells: &[usize]) {
+        let game = stored_game(data).await;
+        let players = [game.player1, game.player2.unwrap()];
+
+        for (turn, cell) in cells.iter().enumerate() {
+            press(data, &format!("tictactoe-game-{}", cell), players[turn % 2]).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn join_starts_game() {
+        let data = games(Some(TictactoeGame::new(PLAYER1, 3)));
+
+        let response = press(&data, "tictactoe-join", PLAYER2).await;
+
+        assert_eq!(
+            content(&response),
+            format!("<@10>'s turn!{}", deadline(&data).await)
+        );
+        let mut rows = (0..3)
+            .map(|y| {
+                (0..3)
+                    .map(|x| format!("tictactoe-game-{}", y * 3 + x))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        rows.push(vec![
+            String::from("tictactoe-resign-1"),
+            String::from("tictactoe-draw-1"),
+        ]);
+        assert_eq!(button_ids(&response), rows);
+    }
+
+    #[tokio::test]
+    async fn cant_join_own_game() {
+        let data = games(Some(TictactoeGame::new(PLAYER1, 3)));
+
+        let response = press(&data, "tictactoe-join", PLAYER1).await;
+
+        assert!(content(&response).starts_with("You can't join your own game!"));
+        assert_eq!(response["data"]["flags"], 64);
+    }
+
+    async fn submit_setup(data: &Arc<RwLock<TypeMap>>, values: &[(&str, &str)]) -> Value {
+        let responder = RecordingResponder::default();
+        let modal = modal_interaction("tictactoe-setup-1", PLAYER1, values);
+        tictactoe_setup(data, &responder, &modal).await.unwrap();
+        responder.last()
+    }
+
+    #[tokio::test]
+    async fn custom_game_form_changes_the_lobby() {
+        let data = games(Some(TictactoeGame::new(PLAYER1, 3)));
+
+        let response = press(&data, "tictactoe-custom", PLAYER2).await;
+        assert!(content(&response).starts_with("Only the player who started"));
+
+        let response = press(&data, "tictactoe-custom", PLAYER1).await;
+        assert_eq!(response["type"], 9);
+        assert_eq!(response["data"]["custom_id"], "tictactoe-setup-1");
+        let inputs = response["data"]["components"].as_array().unwrap();
+        assert_eq!(inputs.len(), 3);
+        assert_eq!(inputs[1]["components"][0]["custom_id"], "win_length");
+        assert_eq!(inputs[1]["components"][0]["value"], "3");
+
+        let response = submit_setup(
+            &data,
+            &[("size", "4"), ("win_length", "3"), ("move_time", "30")],
+        )
+        .await;
+        assert_eq!(response["type"], 7);
+        assert_eq!(
+            content(&response),
+            "<@10> has started a game of tic-tac-toe! Who would like to play?\n\
+             Board: 4x4, 3 in a row to win, 30 seconds per move."
+        );
+        assert_eq!(
+            button_ids(&response),
+            vec![vec!["tictactoe-join", "tictactoe-custom"]]
+        );
+
+        let response = press(&data, "tictactoe-join", PLAYER2).await;
+        assert_eq!(button_ids(&response).len(), 5);
+        let game = stored_game(&data).await;
+        assert_eq!(game.board.win_length(), 3);
+        assert_eq!(game.move_time, Duration::from_secs(30));
+    }
+
+    #[tokio::test]
+    async fn custom_game_form_is_checked() {
+        let data = games(Some(TictactoeGame::new(PLAYER1, 3)));
+
+        let response = submit_setup(
+            &data,
+            &[("size", "3"), ("win_length", "4"), ("move_time", "30")],
+        )
+        .await;
+        assert_eq!(
+            content(&response),
+            "Marks in a row to win can't be more than the board size (3)."
+        );
+        assert_eq!(response["data"]["flags"], 64);
+
+        let response = submit_setup(
+            &data,
+            &[("size", "6"), ("win_length", "3"), ("move_time", "")],
+        )
+        .await;
+        assert_eq!(
+            content(&response),
+            "**Board size** must be between 2 and 5.\n\
+             **Seconds per move** must be a whole number."
+        );
+        assert_eq!(stored_game(&data).await.move_time, DEFAULT_MOVE_TIME);
+    }
+
+    #[tokio::test]
+    async fn move_passes_turn() {
+        let data = games(Some(started_game()));
+
+        let response = press(&data, "tictactoe-game-4", PLAYER1).await;
+
+        assert_eq!(
+            content(&response),
+            format!("<@20>'s turn.{}", deadline(&data).await)
+        );
+        assert_eq!(
+            response["data"]["components"][1]["components"][1]["disabled"],
+            true
+        );
+    }
+
+    #[tokio::test]
+    async fn games_speak_the_guild_language() {
+        let mut game = started_game();
+        game.locale = Locale::negotiate("fr").unwrap();
+        let data = games(Some(game));
+
+        let response = press(&data, "tictactoe-game-4", PLAYER1).await;
+        assert!(content(&response).starts_with("À <@20> de jouer.\nJoue <t:"));
+        assert_eq!(
+            response["data"]["components"][3]["components"][0]["label"],
+            "Abandonner"
+        );
+
+        // Replies only the user sees are in their own language
+        let response = press(&data, "tictactoe-game-0", UserId::new(30)).await;
+        assert!(content(&response).starts_with("That's not your game!"));
+    }
+
+    #[tokio::test]
+    async fn out_of_turn_move_is_rejected() {
+        let data = games(Some(started_game()));
+
+        let response = press(&data, "tictactoe-game-4", PLAYER2).await;
+
+        assert!(content(&response).starts_with("It's not your turn!"));
+    }
+
+    #[tokio::test]
+    async fn late_clicks_are_answered_privately() {
+        let data = games(Some(started_game()));
+
+        play(&data, &[4]).await;
+        let response = press(&data, "tictactoe-game-4", PLAYER2).await;
+        assert!(content(&response).starts_with("That square has already been taken!"));
+        assert_eq!(response["data"]["flags"], 64);
+
+        for (cell, player) in [(0, PLAYER2), (3, PLAYER1), (1, PLAYER2), (5, PLAYER1)] {
+            press(&data, &format!("tictactoe-game-{}", cell), player).await;
+        }
+        assert!(stored_game(&data).await.board.is_over());
+        let response = press(&data, "tictactoe-game-8", PLAYER2).await;
+        assert!(content(&response).starts_with("This game is already over!"));
+    }
+
+    #[tokio::test]
+    async fn completing_a_row_wins() {
+        let data = games(Some(started_game()));
+
+        play(&data, &[0, 3, 1, 4]).await;
+        let response = press(&data, "tictactoe-game-2", PLAYER1).await;
+
+        assert_eq!(content(&response), "<@10> won!\nSeries: <@10> 1 - 0 <@20>");
+        assert_eq!(
+            button_ids(&response)[3],
+            vec!["tictactoe-rematch", "tictactoe-replay"]
+        );
+    }
+
+    #[tokio::test]
+    async fn rematch_waits_for_both_players() {
+        let data = games(Some(started_game()));
+        play(&data, &[0, 3, 1, 4, 2]).await;
+
+        let response = press(&data, "tictactoe-rematch", PLAYER2).await;
+        assert_eq!(content(&response), "\n<@20> wants a rematch!");
+        let response = press(&data, "tictactoe-rematch", PLAYER2).await;
+        assert!(content(&response).starts_with("You've already asked for a rematch!"));
+        let response = press(&data, "tictactoe-rematch", UserId::new(30)).await;
+        assert!(content(&response).starts_with("That's not your game!"));
+
+        let response = press(&data, "tictactoe-rematch", PLAYER1).await;
+        let rematch = GameId(COMPONENT_ID);
+        let rematch_deadline = || async {
+            let games = TictactoeGames::of(&data).await;
+            let deadline = games.with(rematch, |game| game.deadline).unwrap();
+            let deadline = deadline.unwrap().duration_since(UNIX_EPOCH).unwrap();
+            format!("\nMake your move <t:{}:R>.", deadline.as_secs())
+        };
+        assert_eq!(response["type"], 4);
+        assert_eq!(
+            content(&response),
+            format!(
+                "Rematch! <@20>'s turn!\nSeries: <@20> 0 - 1 <@10>{}",
+                rematch_deadline().await
+            )
+        );
+        assert_eq!(button_ids(&response).len(), 4);
+
+        let responder = RecordingResponder::default();
+        let component =
+            reply_component_interaction("tictactoe-game-4", PLAYER2, COMPONENT_ID, PLAYER1);
+        tictactoe_button(&data, &responder, &component)
+            .await
+            .unwrap();
+        assert_eq!(
+            content(&responder.last()),
+            format!(
+                "<@10>'s turn.\nSeries: <@20> 0 - 1 <@10>{}",
+                rematch_deadline().await
+            )
+        );
+
+        // The finished game is left to be replayed
+        let response = press(&data, "tictactoe-rematch", PLAYER2).await;
+        assert!(content(&response).starts_with("That rematch has already started!"));
+        assert_eq!(stored_game(&data).await.history.len(), 5);
+        assert!(stored_game(&data).await.board.is_over());
+    }
+
+    #[tokio::test]
+    async fn full_size_board_moves_to_content_when_over() {
+        let mut game = TictactoeGame::new(PLAYER1, 5);
+        game.start(PLAYER2);
+        game.board = TictactoeBoard::with_marks(5, &[0, 1, 2, 3], &[5, 6, 7, 8]);
+        let data = games(Some(game));
+
+        let response = press(&data, "tictactoe-game-4", PLAYER1).await;
+
+        let first_row = "\u{274C}".repeat(5);
+        assert!(content(&response).contains(&format!("\n\n{}\n", first_row)));
+        assert_eq!(
+            button_ids(&response),
+            vec![vec!["tictactoe-rematch", "tictactoe-replay"]]
+        );
+    }
+
+    async fn challenge(data: &Arc<RwLock<TypeMap>>, opponent: UserId) -> RecordingResponder {
+        let responder = RecordingResponder::default();
+        let command = command_interaction(
+            "tictactoe",
+            PLAYER1,
+            GAME,
+            json!([{ "name": "opponent", "type": 6, "value": opponent.to_string() }]),
+            json!({ "users": { opponent.to_string(): {
+                "id": opponent.to_string(),
+                "username": "opponent",
+                "discriminator": "0001",
+                "avatar": null,
+            } } }),
+        );
+        tictactoe(data, &responder, &command).await.unwrap();
+        responder
+    }
+
+    #[tokio::test]
+    async fn challenge_is_reserved_for_opponent() {
+        let data = games(None);
+
+        let responder = challenge(&data, PLAYER2).await;
+        let response = responder.last();
+        assert_eq!(
+            content(&response),
+            "<@10> has challenged <@20> to a game of tic-tac-toe!"
+        );
+        assert_eq!(
+            button_ids(&response),
+            vec![vec![
+                "tictactoe-accept",
+                "tictactoe-decline",
+                "tictactoe-custom"
+            ]]
+        );
+
+        let response = press(&data, "tictactoe-accept", UserId::new(30)).await;
+        assert!(content(&response).starts_with("This game isn't for you!"));
+        assert_eq!(response["data"]["flags"], 64);
+
+        let response = press(&data, "tictactoe-accept", PLAYER2).await;
+        assert_eq!(
+            content(&response),
+            format!("<@10>'s turn!{}", deadline(&data).await)
+        );
+        assert_eq!(button_ids(&response).len(), 4);
+    }
+
+    #[tokio::test]
+    async fn accepting_twice_leaves_the_game_alone() {
+        let data = games(None);
+        challenge(&data, PLAYER2).await;
+        press(&data, "tictactoe-accept", PLAYER2).await;
+        press(&data, "tictactoe-game-0", PLAYER1).await;
+        let before = stored_game(&data).await;
+
+        let response = press(&data, "tictactoe-accept", PLAYER2).await;
+        assert!(content(&response).starts_with("Someone already joined this game!"));
+        assert_eq!(response["data"]["flags"], 64);
+        let after = stored_game(&data).await;
+        assert_eq!(after.history.len(), 1);
+        assert_eq!(after.start_time, before.start_time);
+        assert_eq!(after.deadline, before.deadline);
+    }
+
+    #[tokio::test]
+    async fn declining_removes_challenge() {
+        let data = games(None);
+        challenge(&data, PLAYER2).await;
+
+        let response = press(&data, "tictactoe-decline", PLAYER2).await;
+
+        assert_eq!(content(&response), "<@20> declined <@10>'s challenge.");
+        assert_eq!(button_ids(&response), Vec::<Vec<String>>::new());
+        assert!(data
+            .read()
+            .await
+            .get::<TictactoeGames>()
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn cant_challenge_yourself() {
+        let data = games(None);
+
+        let response = challenge(&data, PLAYER1).await.last();
+
+        assert!(content(&response).starts_with("You can't challenge yourself!"));
+        assert!(data
+            .read()
+            .await
+            .get::<TictactoeGames>()
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn unaccepted_challenge_expires() {
+        let data = games(None);
+        let responder = challenge(&data, PLAYER2).await;
+
+        tokio::time::sleep(CHALLENGE_TIMEOUT + Duration::from_secs(1)).await;
+
+        let edits = responder.edits();
+        assert_eq!(edits.len(), 1);
+        assert_eq!(
+            edits[0]["content"],
+            "<@10>'s challenge to <@20> has expired."
+        );
+        assert_eq!(edits[0]["components"], json!([]));
+        assert!(data
+            .read()
+            .await
+            .get::<TictactoeGames>()
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn accepted_challenge_doesnt_expire() {
+        let data = games(None);
+        let responder = challenge(&data, PLAYER2).await;
+        press(&data, "tictactoe-accept", PLAYER2).await;
+
+        tokio::time::sleep(CHALLENGE_TIMEOUT + Duration::from_secs(1)).await;
+
+        assert!(responder.edits().is_empty());
+        assert_eq!(data.read().await.get::<TictactoeGames>().unwrap().len(), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn idle_player_forfeits() {
+        let data = games(Some(TictactoeGame::new(PLAYER1, 3)));
+        let responder = RecordingResponder::default();
+        press_with(&data, &responder, "tictactoe-join", PLAYER2).await;
+
+        tokio::time::sleep(DEFAULT_MOVE_TIME + Duration::from_secs(1)).await;
+
+        let edits = responder.edits();
+        assert_eq!(edits.len(), 1);
+        assert_eq!(
+            edits[0]["content"],
+            "<@10> ran out of time! <@20> wins.\nSeries: <@10> 0 - 1 <@20>"
+        );
+        assert_eq!(
+            edits[0]["components"][3]["components"][0]["custom_id"],
+            "tictactoe-rematch"
+        );
+        assert!(stored_game(&data).await.board.is_over());
+    }
+
+    /// The games in `games` after saving and loading them again.
+    fn reloaded(games: &TictactoeGames) -> TictactoeGames {
+        TictactoeGames::from_json(&games.to_json().unwrap()).unwrap()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn restored_game_still_forfeits() {
+        let mut game = started_game();
+        game.board_token = Some(String::from("board"));
+        let saved = TictactoeGames::default();
+        saved.insert(GameId(GAME), game);
+
+        let games = reloaded(&saved);
+        let responder = RecordingResponder::default();
+        resume_timers(&games, |id, token| {
+            assert_eq!((id, token), (GameId(GAME), "board"));
+            responder.clone()
+        });
+        tokio::time::sleep(DEFAULT_MOVE_TIME + Duration::from_secs(1)).await;
+
+        let edits = responder.edits();
+        assert_eq!(edits.len(), 1);
+        assert!(edits[0]["content"]
+            .as_str()
+            .unwrap()
+            .starts_with("<@10> ran out of time! <@20> wins."));
+        assert!(games
+            .with(GameId(GAME), |game| game.board.is_over())
+            .unwrap());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn restored_challenge_still_expires() {
+        let data = games(None);
+        challenge(&data, PLAYER2).await;
+
+        let games = reloaded(&TictactoeGames::of(&data).await);
+        let responder = RecordingResponder::default();
+        resume_timers(&games, |_, _| responder.clone());
+        tokio::time::sleep(CHALLENGE_TIMEOUT + Duration::from_secs(1)).await;
+
+        let edits = responder.edits();
+        assert_eq!(
+            edits[0]["content"],
+            "<@10>'s challenge to <@20> has expired."
+        );
+        assert!(games.is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn moving_restarts_clock() {
+        let data = games(Some(TictactoeGame::new(PLAYER1, 3)));
+        let responder = RecordingResponder::default();
+        press_with(&data, &responder, "tictactoe-join", PLAYER2).await;
+
+        tokio::time::sleep(DEFAULT_MOVE_TIME - Duration::from_secs(10)).await;
+        press_with(&data, &responder, "tictactoe-game-4", PLAYER1).await;
+        tokio::time::sleep(DEFAULT_MOVE_TIME - Duration::from_secs(10)).await;
+        assert!(responder.edits().is_empty());
+
+        tokio::time::sleep(Duration::from_secs(11)).await;
+        let edits = responder.edits();
+        assert_eq!(edits.len(), 1);
+        assert!(edits[0]["content"]
+            .as_str()
+            .unwrap()
+            .starts_with("<@20> ran out of time! <@10> wins."));
+    }
+
+    #[tokio::test]
+    async fn resigning_ends_game() {
+        let data = games(Some(started_game()));
+
+        let response = press(&data, "tictactoe-resign-1", PLAYER2).await;
+
+        assert_eq!(
+            content(&response),
+            "<@20> resigned! <@10> wins.\nSeries: <@10> 1 - 0 <@20>"
+        );
+        assert_eq!(
+            button_ids(&response)[3],
+            vec!["tictactoe-rematch", "tictactoe-replay"]
+        );
+        let response = press(&data, "tictactoe-resign-1", PLAYER1).await;
+        assert_eq!(content(&response), "This game is already over!");
+    }
+
+    #[tokio::test]
+    async fn draw_needs_both_players() {
+        let data = games(Some(started_game()));
+
+        let response = press(&data, "tictactoe-draw-1", PLAYER1).await;
+        assert_eq!(
+            content(&response),
+            format!(
+                "<@10>'s turn.\n<@10> offered a draw.{}",
+                deadline(&data).await
+            )
+        );
+        assert_eq!(
+            button_ids(&response)[3],
+            vec![
+                "tictactoe-resign-1",
+                "tictactoe-drawaccept-1",
+                "tictactoe-drawdecline-1"
+            ]
+        );
+        let response = press(&data, "tictactoe-drawaccept-1", PLAYER1).await;
+        assert!(content(&response).starts_with("You can't accept your own draw offer!"));
+        assert_eq!(response["data"]["flags"], 64);
+
+        let response = press(&data, "tictactoe-drawaccept-1", PLAYER2).await;
+        assert_eq!(
+            content(&response),
+            "It's a draw by agreement!\nSeries: <@10> 0 - 0 <@20> (1 tie)"
+        );
+        assert_eq!(
+            button_ids(&response)[3],
+            vec!["tictactoe-rematch", "tictactoe-replay"]
+        );
+    }
+
+    #[tokio::test]
+    async fn declining_draw_restores_controls() {
+        let data = games(Some(started_game()));
+        press(&data, "tictactoe-draw-1", PLAYER1).await;
+
+        let response = press(&data, "tictactoe-drawdecline-1", PLAYER2).await;
+
+        assert_eq!(
+            content(&response),
+            format!("<@10>'s turn.{}", deadline(&data).await)
+        );
+        assert_eq!(
+            button_ids(&response)[3],
+            vec!["tictactoe-resign-1", "tictactoe-draw-1"]
+        );
+    }
+
+    #[tokio::test]
+    async fn full_size_board_gets_control_message() {
+        let data = games(Some(TictactoeGame::new(PLAYER1, 5)));
+        let responder = RecordingResponder::default();
+        let response = press_with(&data, &responder, "tictactoe-join", PLAYER2).await;
+        assert_eq!(button_ids(&response).len(), 5);
+        let followups = responder.followups();
+        assert_eq!(followups.len(), 1);
+        assert_eq!(
+            button_ids(&json!({ "data": followups[0] })),
+            vec![vec!["tictactoe-resign-1", "tictactoe-draw-1"]]
+        );
+
+        let responder = RecordingResponder::default();
+        let response = press_with(&data, &responder, "tictactoe-resign-1", PLAYER1).await;
+
+        assert_eq!(content(&response), "<@10> resigned! <@20> wins.");
+        assert_eq!(button_ids(&response), Vec::<Vec<String>>::new());
+        let edits = responder.edits_to();
+        assert_eq!(edits.len(), 1);
+        assert_eq!(edits[0].0, "token");
+        assert!(edits[0].1["content"]
+            .as_str()
+            .unwrap()
+            .starts_with("<@10> resigned! <@20> wins.\nSeries: <@10> 0 - 1 <@20>\n\n"));
+        assert_eq!(
+            edits[0].1["components"][0]["components"][0]["custom_id"],
+            "tictactoe-rematch"
+        );
+    }
+
+    #[tokio::test]
+    async fn replay_steps_through_finished_game() {
+        let data = games(Some(started_game()));
+        play(&data, &[0, 3, 1, 4, 2]).await;
+        // Moves are timed by the clock, so give them times that don't depend
+        // on how fast the test runs
+        TictactoeGames::of(&data).await.with(GameId(GAME), |game| {
+            let start = game.start_time.unwrap();
+            for (i, record) in game.history.iter_mut().enumerate() {
+                record.time = start + Duration::from_millis(1500) * (i as u32 + 1);
+            }
+        });
+
+        let responder = RecordingResponder::default();
+        let response = press_with(&data, &responder, "tictactoe-replay", UserId::new(30)).await;
+
+        assert_eq!(response["data"]["flags"], 64);
+        assert_eq!(
+            content(&response),
+            "**Replay:** start of game (5 moves)\n\n\u{2B1C}\u{2B1C}\u{2B1C}\n\u{2B1C}\u{2B1C}\u{2B1C}\n\u{2B1C}\u{2B1C}\u{2B1C}"
+        );
+        assert_eq!(
+            button_ids(&response),
+            vec![vec!["tictactoe-step-1-0", "tictactoe-step-1-1"]]
+        );
+        let files = responder.files();
+        assert_eq!(files[0].filename, "tictactoe-1.txt");
+        let export = String::from_utf8(files[0].data.clone()).unwrap();
+        assert!(export.starts_with("[Game \"Tic-tac-toe\"]\n[Size \"3x3\"]\n"));
+        assert!(export.contains("[X \"10\"]\n[O \"20\"]\n[Result \"1-0\"]\n"));
+        assert!(export.ends_with(
+            "\n\n1. a1 {+1.5s} a2 {+1.5s} 2. b1 {+1.5s} b2 {+1.5s} 3. c1 {+1.5s} 1-0\n"
+        ));
+
+        let response = press(&data, "tictactoe-step-1-4", UserId::new(30)).await;
+        assert_eq!(response["type"], 7);
+        assert_eq!(
+            content(&response),
+            "**Replay:** move 4 of 5: \u{2B55} b2\n\n\u{274C}\u{274C}\u{2B1C}\n\u{2B55}\u{2B55}\u{2B1C}\n\u{2B1C}\u{2B1C}\u{2B1C}"
+        );
+    }
+
+    #[tokio::test]
+    async fn missing_game_has_expired() {
+        let data = games(None);
+
+        let response = press(&data, "tictactoe-game-0", PLAYER1).await;
+
+        assert!(content(&response).starts_with("This game has expired"));
+        assert_eq!(button_ids(&response), Vec::<Vec<String>>::new());
+    }
 }
@@ -0,0 +1,407 @@
+use std::collections::HashSet;
+use std::iter::repeat_with;
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+use serde::{Deserialize, Serialize};
+
+use super::MoveError;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum MinesweeperCell {
+    Safe,
+    Checked,
+    Bomb,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum MinesweeperAction {
+    Reveal(usize),
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum MinesweeperOutcome {
+    /// These cells were cleared and the game continues.
+    Cleared(Vec<usize>),
+    /// These cells were cleared, leaving no safe cells.
+    Won(Vec<usize>),
+    /// A mine was revealed.
+    Lost(usize),
+}
+
+/// A minesweeper board. Mines are placed on the first reveal so that it is
+/// always safe, using `seed` so the same board can be dealt again.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MinesweeperBoard {
+    width: usize,
+    height: usize,
+    mines: usize,
+    seed: u64,
+    cells: Option<Vec<MinesweeperCell>>,
+    over: bool,
+    /// Whether the tiles around the first reveal are kept free of mines too,
+    /// so it always opens up an area.
+    #[serde(default)]
+    opening: bool,
+}
+
+impl MinesweeperBoard {
+    pub fn new(width: usize, height: usize, mines: usize) -> MinesweeperBoard {
+        MinesweeperBoard::with_seed(width, height, mines, rand::random())
+    }
+
+    pub fn with_seed(width: usize, height: usize, mines: usize, seed: u64) -> MinesweeperBoard {
+        MinesweeperBoard {
+            width,
+            height,
+            mines,
+            seed,
+            cells: None,
+            over: false,
+            opening: false,
+        }
+    }
+
+    /// The same board, keeping the tiles around the first reveal clear if
+    /// `opening` is set. Only has an effect before the mines are placed.
+    pub fn with_opening(mut self, opening: bool) -> MinesweeperBoard {
+        self.opening = opening;
+        self
+    }
+
+    /// A board with mines at `mine_indexes`, for tests that need to know
+    /// where they are.
+    #[cfg(test)]
+    pub(crate) fn with_mines(
+        width: usize,
+        height: usize,
+        mine_indexes: &[usize],
+    ) -> MinesweeperBoard {
+        let mut board = MinesweeperBoard::with_seed(width, height, mine_indexes.len(), 0);
+        board.cells = Some(
+            (0..width * height)
+                .map(|i| {
+                    if mine_indexes.contains(&i) {
+                        MinesweeperCell::Bomb
+                    } else {
+                        MinesweeperCell::Safe
+                    }
+                })
+                .collect(),
+        );
+        board
+    }
+
+    /// A fresh board that deals the same mines as this one.
+    pub fn replay(&self) -> MinesweeperBoard {
+        MinesweeperBoard::with_seed(self.width, self.height, self.mines, self.seed)
+            .with_opening(self.opening)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn mines(&self) -> usize {
+        self.mines
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// The tiles, or `None` until the mines are placed.
+    pub fn cells(&self) -> Option<&[MinesweeperCell]> {
+        self.cells.as_deref()
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.over
+    }
+
+    pub fn opening(&self) -> bool {
+        self.opening
+    }
+
+    /// The most mines a board can have while leaving room for the first
+    /// reveal to be safe, and with an `opening`, every tile around it too.
+    /// Boards too small for an opening give up some of it so that at least
+    /// one mine fits.
+    pub fn max_mines(width: usize, height: usize, opening: bool) -> usize {
+        let size = width * height;
+        let safe = if opening {
+            width.min(3) * height.min(3)
+        } else {
+            1
+        };
+        size.saturating_sub(safe).max(1).min(size.saturating_sub(1))
+    }
+
+    /// Lays out the mines from the seed, keeping `safe_cell_index` clear.
+    /// This happens on the first reveal, unless the board is dealt ahead of
+    /// time.
+    pub fn place_mines(&mut self, safe_cell_index: usize) {
+        let rng = &mut StdRng::seed_from_u64(self.seed);
+        let size = self.width * self.height;
+        let mut cells = repeat_with(|| MinesweeperCell::Safe)
+            .take(size)
+            .collect::<Vec<_>>();
+        let mut safe = vec![safe_cell_index];
+        if self.opening {
+            safe.extend(self.adjacent_indexes(safe_cell_index));
+        }
+        let mut bombs = (0..size)
+            .filter(|index| !safe.contains(index))
+            .collect::<Vec<_>>();
+        // Without room for every mine, some of the tiles around are given up
+        if bombs.len() < self.mines {
+            let neighbours = &mut safe[1..];
+            neighbours.shuffle(rng);
+            let missing = self.mines - bombs.len();
+            bombs.extend(neighbours.iter().take(missing));
+        }
+        let bombs = bombs.choose_multiple(rng, self.mines).cloned();
+        for b in bombs {
+            cells[b] = MinesweeperCell::Bomb;
+        }
+        self.cells = Some(cells);
+    }
+
+    pub fn adjacent_indexes(&self, index: usize) -> Vec<usize> {
+        let mut cells = Vec::new();
+
+        let iy = index / self.width;
+        let ix = index % self.width;
+
+        for y in iy as isize - 1..iy as isize + 2 {
+            if !(0..self.height as isize).contains(&y) {
+                continue;
+            }
+            for x in ix as isize - 1..ix as isize + 2 {
+                if !(0..self.width as isize).contains(&x) {
+                    continue;
+                }
+                if x == ix as isize && y == iy as isize {
+                    continue;
+                }
+
+                cells.push(y as usize * self.width + x as usize);
+            }
+        }
+
+        cells
+    }
+
+    /// Number of mines next to `index`. Zero before the first reveal.
+    pub fn adjacent_bombs(&self, index: usize) -> usize {
+        match &self.cells {
+            Some(cells) => self
+                .adjacent_indexes(index)
+                .iter()
+                .filter(|&&c| cells[c] == MinesweeperCell::Bomb)
+                .count(),
+            None => 0,
+        }
+    }
+
+    /// Number of safe cells that have not been revealed yet.
+    pub fn safe_remaining(&self) -> usize {
+        match &self.cells {
+            Some(cells) => cells
+                .iter()
+                .filter(|&&c| c == MinesweeperCell::Safe)
+                .count(),
+            None => self.width * self.height - self.mines,
+        }
+    }
+
+    fn zero_fill(&self, mut set: HashSet<usize>) -> HashSet<usize> {
+        let cells = self.cells.as_ref().unwrap();
+        let prev = set.clone();
+        for s in prev.iter() {
+            if self.adjacent_bombs(*s) == 0 {
+                set.extend(self.adjacent_indexes(*s).iter());
+                set.retain(|&c| cells[c] != MinesweeperCell::Checked);
+            }
+        }
+        if prev == set {
+            return set;
+        }
+
+        self.zero_fill(set)
+    }
+}
+
+/// Reveals a cell, clearing the area around it if it has no adjacent mines.
+/// The board is left untouched if the move is rejected. Unlike tic-tac-toe
+/// there is no player to pass: anyone on the board can reveal any tile, so
+/// there is no turn to check.
+pub fn apply_move(
+    board: &mut MinesweeperBoard,
+    action: MinesweeperAction,
+) -> Result<MinesweeperOutcome, MoveError> {
+    if board.over {
+        return Err(MoveError::GameOver);
+    }
+
+    match action {
+        MinesweeperAction::Reveal(index) => {
+            if index >= board.width * board.height {
+                return Err(MoveError::OutOfBounds);
+            }
+            if board.cells.is_none() {
+                board.place_mines(index);
+            }
+
+            match board.cells.as_ref().unwrap()[index] {
+                MinesweeperCell::Bomb => {
+                    board.over = true;
+                    Ok(MinesweeperOutcome::Lost(index))
+                }
+                MinesweeperCell::Checked => Err(MoveError::Occupied),
+                MinesweeperCell::Safe => {
+                    let fill = board.zero_fill(HashSet::from([index]));
+                    let cells = board.cells.as_mut().unwrap();
+                    for f in &fill {
+                        cells[*f] = MinesweeperCell::Checked;
+                    }
+                    let mut cleared = fill.into_iter().collect::<Vec<_>>();
+                    cleared.sort_unstable();
+
+                    if board.safe_remaining() == 0 {
+                        board.over = true;
+                        Ok(MinesweeperOutcome::Won(cleared))
+                    } else {
+                        Ok(MinesweeperOutcome::Cleared(cleared))
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn zero_fill_clears_open_area() {
+        let mut board = MinesweeperBoard::with_mines(5, 5, &[24]);
+
+        let outcome = apply_move(&mut board, MinesweeperAction::Reveal(0)).unwrap();
+
+        assert_eq!(
+            outcome,
+            MinesweeperOutcome::Won((0..24).collect::<Vec<_>>())
+        );
+    }
+
+    #[test]
+    fn checked_cells_cant_be_revealed() {
+        let mut board = MinesweeperBoard::with_mines(5, 5, &[0, 24]);
+        apply_move(&mut board, MinesweeperAction::Reveal(23)).unwrap();
+
+        assert_eq!(
+            apply_move(&mut board, MinesweeperAction::Reveal(23)),
+            Err(MoveError::Occupied)
+        );
+    }
+
+    #[test]
+    fn max_mines_leaves_room_for_an_opening() {
+        assert_eq!(MinesweeperBoard::max_mines(5, 5, true), 16);
+        assert_eq!(MinesweeperBoard::max_mines(2, 5, true), 4);
+        assert_eq!(MinesweeperBoard::max_mines(3, 3, true), 1);
+        assert_eq!(MinesweeperBoard::max_mines(3, 3, false), 8);
+        assert_eq!(MinesweeperBoard::max_mines(1, 1, true), 0);
+    }
+
+    #[test]
+    fn opening_keeps_neighbours_clear() {
+        let mut board = MinesweeperBoard::with_seed(5, 5, 16, 7).with_opening(true);
+
+        let outcome = apply_move(&mut board, MinesweeperAction::Reveal(12)).unwrap();
+
+        // Every other tile is a mine, so the opening is all there is to clear
+        let mut opening = board.adjacent_indexes(12);
+        opening.push(12);
+        opening.sort_unstable();
+        assert_eq!(outcome, MinesweeperOutcome::Won(opening));
+    }
+
+    #[test]
+    fn opening_shrinks_when_mines_dont_fit() {
+        let mut board = MinesweeperBoard::with_seed(5, 5, 20, 7).with_opening(true);
+
+        apply_move(&mut board, MinesweeperAction::Reveal(12)).unwrap();
+
+        let cells = board.cells.as_ref().unwrap();
+        assert_eq!(cells[12], MinesweeperCell::Checked);
+        let mines = cells
+            .iter()
+            .filter(|&&c| c == MinesweeperCell::Bomb)
+            .count();
+        assert_eq!(mines, 20);
+    }
+
+    #[test]
+    fn same_seed_deals_same_board() {
+        let mut a = MinesweeperBoard::with_seed(5, 5, 10, 42);
+        let mut b = MinesweeperBoard::with_seed(5, 5, 10, 42);
+        apply_move(&mut a, MinesweeperAction::Reveal(12)).unwrap();
+        apply_move(&mut b, MinesweeperAction::Reveal(12)).unwrap();
+
+        assert_eq!(a.cells, b.cells);
+    }
+
+    proptest! {
+        #[test]
+        fn first_reveal_is_safe(
+            seed: u64,
+            mines in 1..24usize,
+            index in 0..25usize,
+            opening: bool,
+        ) {
+            let mut board = MinesweeperBoard::with_seed(5, 5, mines, seed).with_opening(opening);
+
+            let outcome = apply_move(&mut board, MinesweeperAction::Reveal(index)).unwrap();
+
+            let cells = board.cells.as_ref().unwrap();
+            prop_assert_eq!(cells.iter().filter(|&&c| c == MinesweeperCell::Bomb).count(), mines);
+            match outcome {
+                MinesweeperOutcome::Cleared(cleared) | MinesweeperOutcome::Won(cleared) => {
+                    prop_assert!(cleared.contains(&index));
+                    prop_assert!(cleared.iter().all(|&c| cells[c] == MinesweeperCell::Checked));
+                    let neighbours = board.adjacent_indexes(index);
+                    if opening && mines <= 24 - neighbours.len() {
+                        prop_assert!(neighbours.iter().all(|&c| cells[c] != MinesweeperCell::Bomb));
+                    }
+                }
+                MinesweeperOutcome::Lost(_) => prop_assert!(false, "First reveal hit a mine"),
+            }
+        }
+
+        #[test]
+        fn revealing_every_safe_cell_wins(seed: u64, mines in 1..24usize) {
+            let mut board = MinesweeperBoard::with_seed(5, 5, mines, seed);
+            apply_move(&mut board, MinesweeperAction::Reveal(0)).unwrap();
+
+            while !board.over {
+                let cells = board.cells.as_ref().unwrap();
+                let next = cells.iter().position(|&c| c == MinesweeperCell::Safe).unwrap();
+                let outcome = apply_move(&mut board, MinesweeperAction::Reveal(next)).unwrap();
+                prop_assert!(!matches!(outcome, MinesweeperOutcome::Lost(_)));
+            }
+
+            prop_assert_eq!(board.safe_remaining(), 0);
+        }
+    }
+}
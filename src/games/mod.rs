@@ -0,0 +1,31 @@
+//! Game rules, independent of Discord.
+//!
+//! Each game has an `apply_move` taking the board, the player moving when
+//! the game has turns, and their action. It either plays the move or
+//! returns a [`MoveError`], leaving the board untouched.
+
+use std::fmt;
+
+pub mod minesweeper;
+pub mod tictactoe;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MoveError {
+    NotYourTurn,
+    OutOfBounds,
+    Occupied,
+    GameOver,
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            MoveError::NotYourTurn => "Move made out of turn",
+            MoveError::OutOfBounds => "Move is outside the board",
+            MoveError::Occupied => "Cell has already been played",
+            MoveError::GameOver => "Game is already over",
+        })
+    }
+}
+
+impl std::error::Error for MoveError {}
@@ -0,0 +1,293 @@
+use std::iter::repeat_with;
+
+use serde::{Deserialize, Serialize};
+
+use super::MoveError;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum TictactoeCell {
+    X,
+    O,
+}
+
+impl TictactoeCell {
+    pub fn other(self) -> TictactoeCell {
+        match self {
+            TictactoeCell::X => TictactoeCell::O,
+            TictactoeCell::O => TictactoeCell::X,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum TictactoeAction {
+    Place(usize),
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum TictactoeOutcome {
+    /// The game continues with the other player's turn.
+    Continue,
+    /// The mover completed the line made of these cells.
+    Win(Vec<usize>),
+    Tie,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TictactoeBoard {
+    size: usize,
+    cells: Vec<Option<TictactoeCell>>,
+    turn: TictactoeCell,
+    over: bool,
+    /// Marks in a row needed to win, when less than a whole row.
+    #[serde(default)]
+    win_length: Option<usize>,
+}
+
+impl TictactoeBoard {
+    pub fn new(size: usize) -> TictactoeBoard {
+        TictactoeBoard {
+            size,
+            cells: repeat_with(|| None).take(size * size).collect::<Vec<_>>(),
+            turn: TictactoeCell::X,
+            over: false,
+            win_length: None,
+        }
+    }
+
+    /// A board won with `win_length` marks in a row.
+    pub fn with_win_length(size: usize, win_length: usize) -> TictactoeBoard {
+        let mut board = TictactoeBoard::new(size);
+        if win_length < size {
+            board.win_length = Some(win_length);
+        }
+        board
+    }
+
+    /// An empty board with the same rules.
+    pub fn restart(&self) -> TictactoeBoard {
+        TictactoeBoard {
+            win_length: self.win_length,
+            ..TictactoeBoard::new(self.size)
+        }
+    }
+
+    /// Marks in a row needed to win.
+    pub fn win_length(&self) -> usize {
+        self.win_length.unwrap_or(self.size)
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn cells(&self) -> &[Option<TictactoeCell>] {
+        &self.cells
+    }
+
+    /// Whose mark goes next.
+    pub fn turn(&self) -> TictactoeCell {
+        self.turn
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.over
+    }
+
+    /// Ends the game without a winning move, as when a player resigns or
+    /// both agree to a draw.
+    pub fn end(&mut self) {
+        self.over = true;
+    }
+
+    /// A board with X's marks at `x` and O's at `o`, for tests that start
+    /// from a given position.
+    #[cfg(test)]
+    pub(crate) fn with_marks(size: usize, x: &[usize], o: &[usize]) -> TictactoeBoard {
+        let mut board = TictactoeBoard::new(size);
+        for &index in x {
+            board.cells[index] = Some(TictactoeCell::X);
+        }
+        for &index in o {
+            board.cells[index] = Some(TictactoeCell::O);
+        }
+        if x.len() > o.len() {
+            board.turn = TictactoeCell::O;
+        }
+        board
+    }
+
+    /// The run of marks through `cell_index` that wins the game, if any.
+    fn check_win(&self, cell_index: usize) -> Option<Vec<usize>> {
+        let size = self.size as isize;
+        let cell_x = cell_index as isize % size;
+        let cell_y = cell_index as isize / size;
+        let mark = self.cells[cell_index];
+        let is_mark = |x: isize, y: isize| {
+            (0..size).contains(&x)
+                && (0..size).contains(&y)
+                && self.cells[(y * size + x) as usize] == mark
+        };
+
+        // Row, column, diagonal and anti-diagonal
+        [(1, 0), (0, 1), (1, 1), (-1, 1)]
+            .into_iter()
+            .map(|(dx, dy)| {
+                let (mut x, mut y) = (cell_x, cell_y);
+                while is_mark(x - dx, y - dy) {
+                    x -= dx;
+                    y -= dy;
+                }
+                let mut line = Vec::new();
+                while is_mark(x, y) {
+                    line.push((y * size + x) as usize);
+                    x += dx;
+                    y += dy;
+                }
+                line
+            })
+            .find(|line| line.len() >= self.win_length())
+    }
+}
+
+/// Places `player`'s mark, returning how the game stands afterwards. The
+/// board is left untouched if the move is rejected.
+pub fn apply_move(
+    board: &mut TictactoeBoard,
+    player: TictactoeCell,
+    action: TictactoeAction,
+) -> Result<TictactoeOutcome, MoveError> {
+    if board.over {
+        return Err(MoveError::GameOver);
+    }
+    if player != board.turn {
+        return Err(MoveError::NotYourTurn);
+    }
+
+    match action {
+        TictactoeAction::Place(index) => {
+            match board.cells.get(index) {
+                None => return Err(MoveError::OutOfBounds),
+                Some(Some(_)) => return Err(MoveError::Occupied),
+                Some(None) => board.cells[index] = Some(player),
+            }
+
+            if let Some(line) = board.check_win(index) {
+                board.over = true;
+                Ok(TictactoeOutcome::Win(line))
+            } else if board.cells.iter().all(|c| c.is_some()) {
+                board.over = true;
+                Ok(TictactoeOutcome::Tie)
+            } else {
+                board.turn = board.turn.other();
+                Ok(TictactoeOutcome::Continue)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    fn play(board: &mut TictactoeBoard, moves: &[usize]) -> Vec<TictactoeOutcome> {
+        moves
+            .iter()
+            .map(|&i| {
+                let player = board.turn;
+                apply_move(board, player, TictactoeAction::Place(i)).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn diagonal_wins() {
+        let mut board = TictactoeBoard::new(3);
+        let outcomes = play(&mut board, &[2, 0, 4, 1, 6]);
+        assert_eq!(outcomes.last(), Some(&TictactoeOutcome::Win(vec![2, 4, 6])));
+    }
+
+    #[test]
+    fn shorter_runs_win_with_win_length() {
+        let mut board = TictactoeBoard::with_win_length(4, 3);
+        let outcomes = play(&mut board, &[5, 0, 10, 1, 15]);
+        assert_eq!(
+            outcomes.last(),
+            Some(&TictactoeOutcome::Win(vec![5, 10, 15]))
+        );
+
+        let mut board = TictactoeBoard::new(4);
+        let outcomes = play(&mut board, &[5, 0, 10, 1, 15]);
+        assert_eq!(outcomes.last(), Some(&TictactoeOutcome::Continue));
+    }
+
+    #[test]
+    fn full_board_ties() {
+        let mut board = TictactoeBoard::new(3);
+        let outcomes = play(&mut board, &[0, 1, 2, 4, 3, 5, 7, 6, 8]);
+        assert_eq!(outcomes.last(), Some(&TictactoeOutcome::Tie));
+    }
+
+    #[test]
+    fn rejects_invalid_moves() {
+        let mut board = TictactoeBoard::new(3);
+        play(&mut board, &[4]);
+
+        let o = TictactoeCell::O;
+        let x = TictactoeCell::X;
+        assert_eq!(
+            apply_move(&mut board, x, TictactoeAction::Place(0)),
+            Err(MoveError::NotYourTurn)
+        );
+        assert_eq!(
+            apply_move(&mut board, o, TictactoeAction::Place(4)),
+            Err(MoveError::Occupied)
+        );
+        assert_eq!(
+            apply_move(&mut board, o, TictactoeAction::Place(9)),
+            Err(MoveError::OutOfBounds)
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn random_games_end_consistently(
+            size in 2..=5usize,
+            win_length in 2..=5usize,
+            order in Just((0..25).collect::<Vec<usize>>()).prop_shuffle(),
+        ) {
+            let mut board = TictactoeBoard::with_win_length(size, win_length.min(size));
+
+            for (moves, index) in order.into_iter().filter(|&i| i < size * size).enumerate() {
+                let player = board.turn;
+                let outcome = apply_move(&mut board, player, TictactoeAction::Place(index)).unwrap();
+
+                match outcome {
+                    TictactoeOutcome::Continue => {
+                        prop_assert!(!board.over);
+                        prop_assert_eq!(board.turn, player.other());
+                    }
+                    TictactoeOutcome::Win(line) => {
+                        prop_assert!(line.len() >= board.win_length());
+                        prop_assert!(line.contains(&index));
+                        prop_assert!(line.iter().all(|&i| board.cells[i] == Some(player)));
+                        break;
+                    }
+                    TictactoeOutcome::Tie => {
+                        prop_assert_eq!(moves + 1, size * size);
+                        break;
+                    }
+                }
+            }
+
+            prop_assert!(board.over);
+            let player = board.turn;
+            prop_assert_eq!(
+                apply_move(&mut board, player, TictactoeAction::Place(0)),
+                Err(MoveError::GameOver)
+            );
+        }
+    }
+}
@@ -0,0 +1,193 @@
+//! Liveness and readiness checks, served over HTTP so an orchestrator can
+//! restart the bot when it gets stuck.
+
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use hyper::{Body, Method, Request, Response, StatusCode};
+use serenity::gateway::ConnectionStage;
+use serenity::prelude::*;
+
+use crate::http::{self, not_found};
+use crate::ShardManagerContainer;
+
+/// How long a check may wait on a lock before the bot counts as stuck.
+const LOCK_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub struct Health {
+    ready: AtomicBool,
+    /// Where games are saved, if they are.
+    data_dir: Option<PathBuf>,
+}
+
+impl Health {
+    pub fn new(data_dir: Option<PathBuf>) -> Health {
+        Health {
+            ready: AtomicBool::new(false),
+            data_dir,
+        }
+    }
+
+    /// Records that the ready event was received.
+    pub fn set_ready(&self) {
+        self.ready.store(true, Ordering::Relaxed);
+    }
+}
+
+pub struct HealthContainer;
+
+impl TypeMapKey for HealthContainer {
+    type Value = Arc<Health>;
+}
+
+/// Whether the shared data can still be locked, which every interaction
+/// needs.
+async fn alive(data: &RwLock<TypeMap>) -> bool {
+    tokio::time::timeout(LOCK_TIMEOUT, data.read())
+        .await
+        .is_ok()
+}
+
+/// Checks that games can be saved by writing a file to `dir`.
+async fn check_data_dir(dir: &Path) -> std::io::Result<()> {
+    let probe = dir.join(".ready");
+    tokio::fs::create_dir_all(dir).await?;
+    tokio::fs::write(&probe, b"").await?;
+    tokio::fs::remove_file(&probe).await
+}
+
+/// Reasons the bot isn't ready to handle interactions, if there are any.
+pub async fn readiness(data: &RwLock<TypeMap>) -> Vec<String> {
+    let (health, shard_manager) = match tokio::time::timeout(LOCK_TIMEOUT, data.read()).await {
+        Ok(data) => (
+            data.get::<HealthContainer>().cloned(),
+            data.get::<ShardManagerContainer>().cloned(),
+        ),
+        Err(_) => return vec![String::from("Shared data is locked")],
+    };
+
+    let mut problems = Vec::new();
+    match &health {
+        Some(health) if health.ready.load(Ordering::Relaxed) => {}
+        _ => problems.push(String::from("Ready event not received")),
+    }
+
+    match shard_manager {
+        Some(shard_manager) => {
+            let runners = shard_manager.runners.clone();
+            let runners = runners.lock().await;
+            if runners.is_empty() {
+                problems.push(String::from("No shards are running"));
+            }
+            for (id, runner) in runners.iter() {
+                if runner.stage != ConnectionStage::Connected {
+                    problems.push(format!("Shard {} is {}", id.0, runner.stage));
+                }
+            }
+        }
+        None => problems.push(String::from("Shard manager not started")),
+    }
+
+    if let Some(dir) = health.as_ref().and_then(|health| health.data_dir.as_ref()) {
+        if let Err(err) = check_data_dir(dir).await {
+            problems.push(format!("Can't save games to {}: {}", dir.display(), err));
+        }
+    }
+    problems
+}
+
+fn text(status: StatusCode, text: String) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .body(Body::from(text))
+        .unwrap()
+}
+
+async fn respond(data: Arc<RwLock<TypeMap>>, request: Request<Body>) -> Response<Body> {
+    if request.method() != Method::GET {
+        return not_found();
+    }
+
+    match request.uri().path() {
+        "/healthz" if alive(&data).await => text(StatusCode::OK, String::from("ok")),
+        "/healthz" => text(StatusCode::SERVICE_UNAVAILABLE, String::from("stuck")),
+        "/readyz" => {
+            let problems = readiness(&data).await;
+            if problems.is_empty() {
+                text(StatusCode::OK, String::from("ready"))
+            } else {
+                text(StatusCode::SERVICE_UNAVAILABLE, problems.join("\n"))
+            }
+        }
+        _ => not_found(),
+    }
+}
+
+/// The address to serve health checks on, from `HEALTH_ADDR`.
+pub fn addr_from_env() -> Option<SocketAddr> {
+    http::addr_from_env("HEALTH_ADDR")
+}
+
+/// Serves `/healthz` and `/readyz` on `addr` in the background.
+pub fn serve(data: Arc<RwLock<TypeMap>>, addr: SocketAddr) {
+    http::serve("health checks", data, addr, respond);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn get(data: &Arc<RwLock<TypeMap>>, path: &str) -> (StatusCode, String) {
+        let request = Request::get(path).body(Body::empty()).unwrap();
+        let response = respond(data.clone(), request).await;
+        let status = response.status();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn not_ready_until_connected() {
+        let health = Arc::new(Health::new(None));
+        let mut data = TypeMap::new();
+        data.insert::<HealthContainer>(health.clone());
+        let data = Arc::new(RwLock::new(data));
+
+        assert_eq!(
+            get(&data, "/healthz").await,
+            (StatusCode::OK, String::from("ok"))
+        );
+        assert_eq!(
+            get(&data, "/readyz").await,
+            (
+                StatusCode::SERVICE_UNAVAILABLE,
+                String::from("Ready event not received\nShard manager not started")
+            )
+        );
+
+        health.set_ready();
+        assert_eq!(get(&data, "/readyz").await.1, "Shard manager not started");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn stuck_when_data_stays_locked() {
+        let data = Arc::new(RwLock::new(TypeMap::new()));
+        let _lock = data.write().await;
+
+        assert_eq!(
+            get(&data, "/healthz").await.0,
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+    }
+
+    #[tokio::test]
+    async fn checks_data_dir_is_writable() {
+        let dir = std::env::temp_dir().join(format!("slaash-health-{}", std::process::id()));
+        assert!(check_data_dir(&dir).await.is_ok());
+        std::fs::write(dir.join("games"), b"").unwrap();
+        assert!(check_data_dir(&dir.join("games")).await.is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,63 @@
+//! A small HTTP server for the endpoints used to run the bot in production.
+
+use std::convert::Infallible;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server, StatusCode};
+use serenity::prelude::*;
+
+use tracing::{error, info, warn};
+
+/// The address in the environment variable `var`, if it is set and valid.
+pub fn addr_from_env(var: &str) -> Option<SocketAddr> {
+    let addr = dotenvy::var(var).ok()?;
+    match addr.parse() {
+        Ok(addr) => Some(addr),
+        Err(err) => {
+            warn!("Invalid {} '{}': {}", var, addr, err);
+            None
+        }
+    }
+}
+
+pub fn not_found() -> Response<Body> {
+    Response::builder()
+        .status(StatusCode::NOT_FOUND)
+        .body(Body::empty())
+        .unwrap()
+}
+
+/// Answers requests on `addr` with `respond` in the background. `name` is
+/// used to log what is being served.
+pub fn serve<F, R>(name: &'static str, data: Arc<RwLock<TypeMap>>, addr: SocketAddr, respond: F)
+where
+    F: Fn(Arc<RwLock<TypeMap>>, Request<Body>) -> R + Copy + Send + Sync + 'static,
+    R: Future<Output = Response<Body>> + Send + 'static,
+{
+    let make_service = make_service_fn(move |_| {
+        let data = data.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| {
+                let response = respond(data.clone(), request);
+                async move { Ok::<_, Infallible>(response.await) }
+            }))
+        }
+    });
+
+    let server = match Server::try_bind(&addr) {
+        Ok(server) => server.serve(make_service),
+        Err(err) => {
+            error!("Failed to serve {} on {}: {}", name, addr, err);
+            return;
+        }
+    };
+    info!("Serving {} on http://{}", name, addr);
+    tokio::spawn(async move {
+        if let Err(err) = server.await {
+            error!("{} server failed: {}", name, err);
+        }
+    });
+}
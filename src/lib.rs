@@ -0,0 +1,314 @@
+pub mod commands;
+pub mod games;
+pub mod health;
+pub mod http;
+pub mod locale;
+pub mod logging;
+pub mod metrics;
+pub mod ratelimit;
+pub mod records;
+pub mod reporting;
+pub mod shutdown;
+pub mod store;
+
+use std::sync::Arc;
+use std::time::Instant;
+
+use serenity::async_trait;
+use serenity::builder::{CreateInteractionResponse, CreateInteractionResponseMessage};
+use serenity::gateway::ShardManager;
+use serenity::http::Http;
+use serenity::model::application::{
+    CommandInteraction, ComponentInteraction, Interaction, ModalInteraction,
+};
+use serenity::model::gateway::Ready;
+use serenity::model::id::GuildId;
+use serenity::prelude::*;
+
+use tracing::{error, info, Instrument};
+
+use crate::commands::blackjack::*;
+use crate::commands::error::*;
+use crate::commands::help::*;
+use crate::commands::minesweeper::*;
+use crate::commands::ping::*;
+use crate::commands::responder::*;
+use crate::commands::tictactoe::*;
+use crate::health::*;
+use crate::locale::{tr, Locale};
+use crate::logging::*;
+use crate::metrics::*;
+use crate::ratelimit::*;
+use crate::reporting::*;
+use crate::shutdown::*;
+
+pub struct ShardManagerContainer;
+
+impl TypeMapKey for ShardManagerContainer {
+    type Value = Arc<ShardManager>;
+}
+
+pub struct Handler;
+
+pub type Error = Box<dyn std::error::Error + Send + Sync>;
+
+#[async_trait]
+impl EventHandler for Handler {
+    async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
+        let span = interaction_span(&interaction);
+        handle_interaction(&ctx.data, &ctx.http, interaction)
+            .instrument(span)
+            .await
+    }
+
+    async fn ready(&self, ctx: Context, ready: Ready) {
+        info!("{} is connected!", ready.user.name);
+        if let Some(health) = ctx.data.read().await.get::<HealthContainer>() {
+            health.set_ready();
+        }
+
+        let guild_id = GuildId::new(567206658070020107);
+
+        let new_commands = guild_id
+            .set_commands(&ctx.http, commands::register())
+            .await
+            .unwrap();
+
+        info!(
+            "Registered commands: {:?}",
+            new_commands.into_iter().map(|c| c.name).collect::<Vec<_>>()
+        );
+    }
+}
+
+/// Whether pressing the button `custom_id` starts a game, which is refused
+/// while shutting down like the commands that start games.
+fn starts_game(custom_id: &str) -> bool {
+    let mut parts = custom_id.split('-');
+    matches!(
+        (parts.next(), parts.next(), parts.next()),
+        (Some("tictactoe"), Some("join" | "accept" | "rematch"), _)
+            | (Some("minesweeper"), _, Some("start"))
+    )
+}
+
+/// Whether pressing the button `custom_id` puts the player in another game,
+/// which counts towards how many they can play at once.
+fn joins_game(custom_id: &str) -> bool {
+    let mut parts = custom_id.split('-');
+    matches!(
+        (parts.next(), parts.next(), parts.next()),
+        (Some("tictactoe"), Some("join" | "accept" | "rematch"), _)
+            | (Some("minesweeper"), _, Some("join"))
+    )
+}
+
+/// Runs `command`, unless it would start a game that can't be started now.
+async fn run_command(
+    data: &RwLock<TypeMap>,
+    responder: &impl Responder,
+    command: &CommandInteraction,
+    locale: Locale,
+) -> Result<(), Error> {
+    match command.data.name.as_str() {
+        "minesweeper" | "tictactoe" if is_shutting_down(data).await => {
+            refuse_game(responder, locale).await
+        }
+        "minesweeper" | "tictactoe" if has_too_many_games(data, command.user.id).await => {
+            refuse_another_game(responder, locale).await
+        }
+        "ping" => ping(responder, command).await,
+        "error" => fail(command).await,
+        "minesweeper" => minesweeper(data, responder, command).await,
+        "blackjack" => blackjack(responder, command).await,
+        "tictactoe" => tictactoe(data, responder, command).await,
+        "help" => help(responder, command).await,
+
+        _ => Err("Command not implemented".into()),
+    }
+}
+
+/// Answers a press of `component`, unless it would start or join a game
+/// that can't be played now.
+async fn run_component(
+    data: &RwLock<TypeMap>,
+    responder: &impl Responder,
+    component: &ComponentInteraction,
+    locale: Locale,
+) -> Result<(), Error> {
+    let custom_id = component.data.custom_id.as_str();
+    match custom_id.split('-').next().unwrap() {
+        _ if starts_game(custom_id) && is_shutting_down(data).await => {
+            refuse_game(responder, locale).await
+        }
+        _ if joins_game(custom_id) && has_too_many_games(data, component.user.id).await => {
+            refuse_another_game(responder, locale).await
+        }
+        "minesweeper" => minesweeper_button(data, responder, component).await,
+        "blackjack" => blackjack_button(data, responder, component).await,
+        "tictactoe" => tictactoe_button(data, responder, component).await,
+        "help" => help_select(responder, component).await,
+
+        // Ideas: connect 4 (or 3), liars dice, kakurasu
+        _ => Err("Unknown message component id".into()),
+    }
+}
+
+/// Applies the form `modal` to the game it was opened from.
+async fn run_modal(
+    data: &RwLock<TypeMap>,
+    responder: &impl Responder,
+    modal: &ModalInteraction,
+) -> Result<(), Error> {
+    match modal.data.custom_id.split('-').next().unwrap() {
+        "minesweeper" => minesweeper_setup(data, responder, modal).await,
+        "tictactoe" => tictactoe_setup(data, responder, modal).await,
+
+        _ => Err("Unknown modal id".into()),
+    }
+}
+
+/// Tells the user their interaction failed, giving the id of `report`, then
+/// logs the report and posts it to the admin log channel.
+async fn answer_failure(
+    data: &RwLock<TypeMap>,
+    http: &Http,
+    responder: &impl Responder,
+    locale: Locale,
+    report: ErrorReport,
+) {
+    // Commands are named by themselves, the rest by their custom id
+    let (message, name) = match &report.custom_id {
+        Some(custom_id) => ("component-failed", custom_id),
+        None => ("command-failed", &report.name),
+    };
+    error!(
+        "{} '{}' failed ({}): {}",
+        report.kind, name, report.correlation_id, report.error
+    );
+    let content = tr!(
+        locale,
+        message,
+        error = report.error.clone(),
+        id = report.correlation_id.clone()
+    );
+    responder
+        .create_interaction_response(CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new()
+                .content(content)
+                .ephemeral(true),
+        ))
+        .await
+        .unwrap_or_else(|err| error!("Failed to send error message: {}", err));
+
+    report_error(data, http, report).await;
+}
+
+/// Answers an interaction. This is all `interaction_create` does, kept apart
+/// so interactions can be handled without a gateway connection.
+pub async fn handle_interaction(
+    data: &RwLock<TypeMap>,
+    http: &Arc<Http>,
+    interaction: Interaction,
+) {
+    match interaction {
+        Interaction::Command(command) => {
+            let command_name = command.data.name.clone();
+            let command_name = command_name.as_str();
+            let responder =
+                InteractionResponder::new(http.clone(), command.id, command.token.clone());
+            let locale = Locale::of_user(&command.locale);
+            let started = Instant::now();
+            let throttled = throttle(
+                data,
+                "command",
+                command_name,
+                command.user.id,
+                command.guild_id,
+            );
+            let result = match throttled.await {
+                Some(wait) => slow_down(&responder, wait, locale).await,
+                None => run_command(data, &responder, &command, locale).await,
+            };
+            record_interaction(data, "command", command_name, started.elapsed(), &result).await;
+
+            if let Err(err) = result {
+                let report = ErrorReport {
+                    kind: "Command",
+                    name: String::from(command_name),
+                    user: command.user.id,
+                    guild: command.guild_id,
+                    custom_id: None,
+                    error: err.to_string(),
+                    correlation_id: correlation_id(),
+                };
+                answer_failure(data, http, &responder, locale, report).await;
+            }
+        }
+        Interaction::Component(component) => {
+            let component_name = component.data.custom_id.split('-').next().unwrap();
+            let responder =
+                InteractionResponder::new(http.clone(), component.id, component.token.clone());
+            let locale = Locale::of_user(&component.locale);
+            let started = Instant::now();
+            let throttled = throttle(
+                data,
+                "component",
+                component_name,
+                component.user.id,
+                component.guild_id,
+            );
+            let result = match throttled.await {
+                Some(wait) => slow_down(&responder, wait, locale).await,
+                None => run_component(data, &responder, &component, locale).await,
+            };
+            record_interaction(
+                data,
+                "component",
+                component_name,
+                started.elapsed(),
+                &result,
+            )
+            .await;
+
+            if let Err(err) = result {
+                let report = ErrorReport {
+                    kind: "Component",
+                    name: String::from(component_name),
+                    user: component.user.id,
+                    guild: component.guild_id,
+                    custom_id: Some(component.data.custom_id.clone()),
+                    error: err.to_string(),
+                    correlation_id: correlation_id(),
+                };
+                answer_failure(data, http, &responder, locale, report).await;
+            }
+        }
+        Interaction::Modal(modal) => {
+            let modal_name = modal.data.custom_id.split('-').next().unwrap();
+            let responder = InteractionResponder::new(http.clone(), modal.id, modal.token.clone());
+            let locale = Locale::of_user(&modal.locale);
+            let started = Instant::now();
+            let throttled = throttle(data, "modal", modal_name, modal.user.id, modal.guild_id);
+            let result = match throttled.await {
+                Some(wait) => slow_down(&responder, wait, locale).await,
+                None => run_modal(data, &responder, &modal).await,
+            };
+            record_interaction(data, "modal", modal_name, started.elapsed(), &result).await;
+
+            if let Err(err) = result {
+                let report = ErrorReport {
+                    kind: "Modal",
+                    name: String::from(modal_name),
+                    user: modal.user.id,
+                    guild: modal.guild_id,
+                    custom_id: Some(modal.data.custom_id.clone()),
+                    error: err.to_string(),
+                    correlation_id: correlation_id(),
+                };
+                answer_failure(data, http, &responder, locale, report).await;
+            }
+        }
+        _ => error!("Unexpected interaction type"),
+    }
+}
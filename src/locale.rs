@@ -0,0 +1,283 @@
+//! Translations of everything the bot says, from the Fluent files in
+//! `locales`.
+//!
+//! Replies only the user can see are in their own locale, while messages
+//! everyone in the channel sees are in the guild's.
+
+use std::fmt;
+use std::sync::LazyLock;
+
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::{FluentArgs, FluentResource};
+use serde::{Deserialize, Serialize};
+use serenity::builder::{CreateCommand, CreateCommandOption};
+use unic_langid::LanguageIdentifier;
+
+use tracing::error;
+
+/// Every translation, named by its Discord locale. The first is the default,
+/// used for anything the others are missing.
+const LOCALES: [(&str, &str); 2] = [
+    ("en-US", include_str!("../locales/en-US.ftl")),
+    ("fr", include_str!("../locales/fr.ftl")),
+];
+
+static BUNDLES: LazyLock<Vec<FluentBundle<FluentResource>>> = LazyLock::new(|| {
+    LOCALES
+        .iter()
+        .map(|(code, source)| {
+            let language: LanguageIdentifier = code.parse().unwrap();
+            let resource = FluentResource::try_new(source.to_string())
+                .unwrap_or_else(|(_, errors)| panic!("Invalid {} messages: {:?}", code, errors));
+            let mut bundle = FluentBundle::new_concurrent(vec![language]);
+            // Isolation marks around arguments would break mentions
+            bundle.set_use_isolating(false);
+            bundle.add_resource(resource).unwrap();
+            bundle
+        })
+        .collect()
+});
+
+/// Translates a message into a `Locale`, with the message's arguments given
+/// as `name = value`.
+macro_rules! tr {
+    ($locale:expr, $id:expr) => {
+        $locale.text($id, None)
+    };
+    ($locale:expr, $id:expr, $($name:ident = $value:expr),+ $(,)?) => {{
+        let mut args = fluent_bundle::FluentArgs::new();
+        $(args.set(stringify!($name), $value);)+
+        $locale.text($id, Some(&args))
+    }};
+}
+pub(crate) use tr;
+
+/// One of the translations in `LOCALES`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub struct Locale(usize);
+
+impl Locale {
+    /// Every translation, the default first.
+    pub fn all() -> impl Iterator<Item = Locale> {
+        (0..LOCALES.len()).map(Locale)
+    }
+
+    /// The closest translation to a Discord locale, like `en-GB` or `fr`.
+    pub fn negotiate(requested: &str) -> Option<Locale> {
+        let language = |code: &str| code.split('-').next().unwrap().to_lowercase();
+        LOCALES
+            .iter()
+            .position(|(code, _)| code.eq_ignore_ascii_case(requested))
+            .or_else(|| {
+                LOCALES
+                    .iter()
+                    .position(|(code, _)| language(code) == language(requested))
+            })
+            .map(Locale)
+    }
+
+    /// The locale for a reply only the user can see.
+    pub fn of_user(locale: &str) -> Locale {
+        Locale::negotiate(locale).unwrap_or_default()
+    }
+
+    /// The locale for a message everyone in the channel can see, which is the
+    /// user's own outside of guilds.
+    pub fn of_guild(guild_locale: Option<&str>, locale: &str) -> Locale {
+        guild_locale
+            .and_then(Locale::negotiate)
+            .unwrap_or_else(|| Locale::of_user(locale))
+    }
+
+    pub fn code(self) -> &'static str {
+        LOCALES[self.0].0
+    }
+
+    fn is_default(self) -> bool {
+        self.0 == 0
+    }
+
+    /// The message `id`, or an attribute of it as `id.attribute`, from this
+    /// translation or else the default one.
+    pub fn text(self, id: &str, args: Option<&FluentArgs>) -> String {
+        self.find(id, args).unwrap_or_else(|| {
+            error!("Missing message {}", id);
+            id.to_string()
+        })
+    }
+
+    /// Like `text`, but for messages that only some ids have, so a missing
+    /// one isn't an error.
+    pub fn find(self, id: &str, args: Option<&FluentArgs>) -> Option<String> {
+        self.lookup(id, args)
+            .or_else(|| Locale::default().lookup(id, args))
+    }
+
+    /// The message `id` from this translation only.
+    fn lookup(self, id: &str, args: Option<&FluentArgs>) -> Option<String> {
+        let bundle = &BUNDLES[self.0];
+        let (id, attribute) = match id.split_once('.') {
+            Some((id, attribute)) => (id, Some(attribute)),
+            None => (id, None),
+        };
+        let message = bundle.get_message(id)?;
+        let pattern = match attribute {
+            Some(attribute) => message.get_attribute(attribute)?.value(),
+            None => message.value()?,
+        };
+
+        let mut errors = Vec::new();
+        let text = bundle.format_pattern(pattern, args, &mut errors);
+        if !errors.is_empty() {
+            error!("Failed to format message {}: {:?}", id, errors);
+        }
+        Some(text.into_owned())
+    }
+}
+
+impl fmt::Display for Locale {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+impl From<String> for Locale {
+    fn from(code: String) -> Locale {
+        Locale::of_user(&code)
+    }
+}
+
+impl From<Locale> for String {
+    fn from(locale: Locale) -> String {
+        locale.code().to_string()
+    }
+}
+
+/// The message `id` in every other locale that has it, for Discord's
+/// `*_localizations` fields.
+pub fn localizations(id: &str) -> Vec<(&'static str, String)> {
+    Locale::all()
+        .filter(|locale| !locale.is_default())
+        .filter_map(|locale| Some((locale.code(), locale.lookup(id, None)?)))
+        .collect()
+}
+
+/// Commands and options with their descriptions, and any translated names,
+/// taken from the message `id`.
+pub trait Localized {
+    fn localized(self, id: &str) -> Self;
+}
+
+impl Localized for CreateCommand {
+    fn localized(mut self, id: &str) -> Self {
+        for (locale, name) in localizations(&format!("{}.name", id)) {
+            self = self.name_localized(locale, name);
+        }
+        for (locale, description) in localizations(id) {
+            self = self.description_localized(locale, description);
+        }
+        self.description(Locale::default().text(id, None))
+    }
+}
+
+impl Localized for CreateCommandOption {
+    fn localized(mut self, id: &str) -> Self {
+        for (locale, name) in localizations(&format!("{}.name", id)) {
+            self = self.name_localized(locale, name);
+        }
+        for (locale, description) in localizations(id) {
+            self = self.description_localized(locale, description);
+        }
+        self.description(Locale::default().text(id, None))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use serenity::json::json;
+
+    use super::*;
+
+    #[test]
+    fn picks_the_closest_translation() {
+        let french = Locale::negotiate("fr").unwrap();
+        assert_eq!(french.code(), "fr");
+        assert_eq!(Locale::negotiate("en-GB"), Some(Locale::default()));
+        assert_eq!(Locale::negotiate("ja"), None);
+        assert_eq!(Locale::of_user("ja"), Locale::default());
+        assert_eq!(Locale::of_guild(Some("fr"), "en-US"), french);
+        assert_eq!(Locale::of_guild(None, "fr"), french);
+    }
+
+    #[test]
+    fn every_message_is_translated() {
+        let ids = |locale: Locale| {
+            let source = LOCALES[locale.0].1;
+            let resource = FluentResource::try_new(source.to_string()).unwrap();
+            resource
+                .entries()
+                .filter_map(|entry| match entry {
+                    fluent_syntax::ast::Entry::Message(message) => {
+                        Some(message.id.name.to_string())
+                    }
+                    _ => None,
+                })
+                .collect::<BTreeSet<_>>()
+        };
+
+        let default = ids(Locale::default());
+        for locale in Locale::all().skip(1) {
+            assert_eq!(ids(locale), default, "{} is missing messages", locale);
+        }
+    }
+
+    #[test]
+    fn formats_arguments() {
+        let english = Locale::default();
+        let french = Locale::negotiate("fr").unwrap();
+
+        assert_eq!(
+            tr!(english, "tictactoe-first-turn", player = "<@10>"),
+            "<@10>'s turn!"
+        );
+        assert_eq!(
+            tr!(french, "tictactoe-first-turn", player = "<@10>"),
+            "À <@10> de jouer !"
+        );
+        assert_eq!(
+            tr!(english, "slow-down", seconds = 1),
+            "Slow down! Try again in 1 second."
+        );
+        assert_eq!(
+            tr!(french, "slow-down", seconds = 3),
+            "Doucement ! Réessaie dans 3 secondes."
+        );
+        assert_eq!(tr!(french, "command-tictactoe.name"), "morpion");
+    }
+
+    #[test]
+    fn localizes_commands() {
+        let command = CreateCommand::new("tictactoe").localized("command-tictactoe");
+        let command = serde_json::to_value(&command).unwrap();
+
+        assert_eq!(
+            command["description"],
+            "Play a classic game of tic-tac-toe."
+        );
+        assert_eq!(command["name_localizations"], json!({"fr": "morpion"}));
+        assert_eq!(
+            command["description_localizations"],
+            json!({"fr": "Joue une partie classique de morpion."})
+        );
+    }
+
+    #[test]
+    fn saves_locale_codes() {
+        let french = Locale::negotiate("fr").unwrap();
+        assert_eq!(serde_json::to_string(&french).unwrap(), "\"fr\"");
+        assert_eq!(serde_json::from_str::<Locale>("\"fr\"").unwrap(), french);
+    }
+}
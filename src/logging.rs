@@ -0,0 +1,144 @@
+//! Log output, configured from the environment.
+
+use serenity::model::application::Interaction;
+use tracing::{field, info_span, warn, Span};
+use tracing_subscriber::EnvFilter;
+
+/// Sets up logging. `RUST_LOG` picks what is logged, `info` and up by
+/// default, and `LOG_FORMAT` how: `json` for one object per line, `pretty`
+/// for readable multi-line entries, or compact lines when unset.
+pub fn init() {
+    let filter = dotenvy::var("RUST_LOG")
+        .ok()
+        .and_then(|directives| EnvFilter::try_new(directives).ok())
+        .unwrap_or_else(|| EnvFilter::new("info"));
+    let builder = tracing_subscriber::fmt().with_env_filter(filter);
+
+    let format = dotenvy::var("LOG_FORMAT").unwrap_or_default();
+    match format.as_str() {
+        "json" => builder.json().with_current_span(true).init(),
+        "pretty" => builder.pretty().init(),
+        _ => builder.compact().init(),
+    }
+    if !["", "json", "pretty"].contains(&format.as_str()) {
+        warn!("Unknown LOG_FORMAT '{}', logging compact lines", format);
+    }
+}
+
+/// A span for everything logged while handling `interaction`. Game handlers
+/// fill in `game_id` once they know it.
+pub fn interaction_span(interaction: &Interaction) -> Span {
+    let span = info_span!(
+        "interaction",
+        id = field::Empty,
+        guild_id = field::Empty,
+        channel_id = field::Empty,
+        user_id = field::Empty,
+        command = field::Empty,
+        custom_id = field::Empty,
+        game_id = field::Empty,
+    );
+
+    let (id, guild, channel, user) = match interaction {
+        Interaction::Command(command) => {
+            span.record("command", command.data.name.as_str());
+            (
+                command.id.get(),
+                command.guild_id,
+                command.channel_id,
+                command.user.id,
+            )
+        }
+        Interaction::Component(component) => {
+            span.record("custom_id", component.data.custom_id.as_str());
+            (
+                component.id.get(),
+                component.guild_id,
+                component.channel_id,
+                component.user.id,
+            )
+        }
+        Interaction::Modal(modal) => {
+            span.record("custom_id", modal.data.custom_id.as_str());
+            (
+                modal.id.get(),
+                modal.guild_id,
+                modal.channel_id,
+                modal.user.id,
+            )
+        }
+        _ => return span,
+    };
+    span.record("id", id);
+    if let Some(guild) = guild {
+        span.record("guild_id", guild.get());
+    }
+    span.record("channel_id", channel.get());
+    span.record("user_id", user.get());
+    span
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use std::sync::{Arc, Mutex};
+
+    use serde_json::{json, Value};
+    use serenity::model::id::UserId;
+    use tracing::info;
+
+    use super::*;
+    use crate::commands::responder::testing::component_interaction;
+    use crate::store::GameId;
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for Buffer {
+        fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(bytes)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn logs_carry_the_interaction() {
+        let buffer = Buffer::default();
+        let writer = buffer.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .json()
+            .with_current_span(true)
+            .with_writer(move || writer.clone())
+            .finish();
+        let interaction = Interaction::Component(component_interaction(
+            "tictactoe-4",
+            UserId::new(10),
+            7,
+            UserId::new(10),
+        ));
+
+        tracing::subscriber::with_default(subscriber, || {
+            let _entered = interaction_span(&interaction).entered();
+            GameId(7).record();
+            info!("Pressed");
+        });
+
+        let output = buffer.0.lock().unwrap();
+        let line: Value = serde_json::from_slice(&output).unwrap();
+        assert_eq!(line["fields"]["message"], "Pressed");
+        assert_eq!(
+            line["span"],
+            json!({
+                "name": "interaction",
+                "id": 900000000000000001u64,
+                "channel_id": 900000000000000003u64,
+                "user_id": 10,
+                "custom_id": "tictactoe-4",
+                "game_id": 7,
+            })
+        );
+    }
+}
@@ -1,153 +1,59 @@
-mod commands;
-
-use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
-use serenity::async_trait;
-use serenity::client::bridge::gateway::ShardManager;
-use serenity::model::gateway::Ready;
-use serenity::model::id::GuildId;
-use serenity::model::interactions::{
-    application_command::ApplicationCommandOptionType, Interaction,
-};
+use serenity::model::id::InteractionId;
 use serenity::prelude::*;
 use serenity::Client;
 
 use tracing::{error, info};
 
-use crate::commands::blackjack::*;
-use crate::commands::error::*;
-use crate::commands::minesweeper::*;
-use crate::commands::ping::*;
-use crate::commands::tictactoe::*;
-
-pub struct ShardManagerContainer;
-
-impl TypeMapKey for ShardManagerContainer {
-    type Value = Arc<Mutex<ShardManager>>;
+use slaash::commands::minesweeper::MinesweeperGame;
+use slaash::commands::responder::InteractionResponder;
+use slaash::commands::tictactoe::{resume_timers, TictactoeGame};
+use slaash::health::{self, Health, HealthContainer};
+use slaash::logging;
+use slaash::metrics::{self, Metrics, MetricsContainer};
+use slaash::ratelimit::{Limits, RateLimiter, RateLimiterContainer};
+use slaash::records::{self, Records, RecordsContainer};
+use slaash::reporting::{ErrorReporter, ErrorReporterContainer};
+use slaash::shutdown;
+use slaash::store::{maintain, Game, GameStore};
+use slaash::{Handler, ShardManagerContainer};
+
+/// Loads the saved records, starting over if they can't be read.
+fn load_records(dir: Option<&Path>) -> Records {
+    let dir = match dir {
+        Some(dir) => dir,
+        None => return Records::default(),
+    };
+    Records::load(dir).unwrap_or_else(|err| {
+        error!("Failed to load records: {}", err);
+        Records::default()
+    })
 }
 
-struct Handler;
-
-type Error = Box<dyn std::error::Error + Send + Sync>;
-
-#[async_trait]
-impl EventHandler for Handler {
-    async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
-        match interaction {
-            Interaction::ApplicationCommand(command) => {
-                let command_name = command.data.name.clone();
-                let command_name = command_name.as_str();
-                let result: Result<(), Error> = match command_name {
-                    "ping" => ping(&ctx, &command).await,
-                    "error" => fail(&ctx, &command).await,
-                    "minesweeper" => minesweeper(&ctx, &command).await,
-                    "blackjack" => blackjack(&ctx, &command).await,
-                    "tictactoe" => tictactoe(&ctx, &command).await,
-
-                    _ => Err("Command not implemented".into()),
-                };
-
-                if let Err(err) = result {
-                    error!("Command '{}' failed: {}", command_name, err);
-                    command.create_interaction_response(&ctx.http, |response| {
-                        response.interaction_response_data(|data| {
-                            data
-                                .content(format!("There was an issue running your command.\n```{}```\nTry running it again or report the issue.", err))
-                                .ephemeral(true)
-                        })
-                    })
-                    .await
-                    .unwrap_or_else(|err| error!("Failed to send error message: {}", err))
-                }
-            }
-            Interaction::MessageComponent(component) => {
-                let component_name = component.data.custom_id.split('-').next().unwrap();
-                let result: Result<(), Error> = match component_name {
-                    "minesweeper" => minesweeper_button(&ctx, &component).await,
-                    "blackjack" => blackjack_button(&ctx, &component).await,
-                    "tictactoe" => tictactoe_button(&ctx, &component).await,
-
-                    // Ideas: connect 4 (or 3), liars dice, kakurasu
-                    _ => Err("Unknown message component id".into()),
-                };
-
-                if let Err(err) = result {
-                    error!("Component '{}' failed: {}", component.data.custom_id, err);
-                    component.create_interaction_response(&ctx.http, |response| {
-                        response.interaction_response_data(|data| {
-                            data
-                                .content(format!("There was an issue handling your interaction.\n```{}```\nTry again or again or report the issue.", err))
-                                .ephemeral(true)
-                        })
-                    })
-                    .await
-                    .unwrap_or_else(|err| error!("Failed to send error message: {}", err))
-                }
-            }
-            _ => error!("Unexpected interaction type"),
+/// Loads the saved `T` games, starting over if they can't be read.
+fn load_games<T: Game>(dir: Option<&Path>) -> GameStore<T> {
+    let dir = match dir {
+        Some(dir) => dir,
+        None => return GameStore::default(),
+    };
+    match GameStore::load(dir) {
+        Ok(store) => {
+            info!("Loaded {} {} games", store.len(), T::NAME);
+            store
+        }
+        Err(err) => {
+            error!("Failed to load {} games: {}", T::NAME, err);
+            GameStore::default()
         }
-    }
-
-    async fn ready(&self, ctx: Context, ready: Ready) {
-        info!("{} is connected!", ready.user.name);
-
-        let guild_id = GuildId(567206658070020107);
-
-        let new_commands = GuildId::set_application_commands(&guild_id, &ctx.http, |commands| {
-            commands
-                .create_application_command(|command| {
-                    command.name("ping").description("Pong hopefully.")
-                })
-                .create_application_command(|command| {
-                    command
-                        .name("minesweeper")
-                        .description("Clear tiles until you win, but dont hit a mine!")
-                        .create_option(|option| {
-                            option
-                                .name("mines")
-                                .description("Number of mines.")
-                                .kind(ApplicationCommandOptionType::Integer)
-                                .min_int_value(1)
-                                .max_int_value(23)
-                        })
-                })
-                .create_application_command(|command| {
-                    command
-                        .name("blackjack")
-                        .description("Start a game of blackjack.")
-                })
-                .create_application_command(|command| {
-                    command
-                        .name("tictactoe")
-                        .description("Play a classic game of tic-tac-toe.")
-                        .create_option(|option| {
-                            option
-                                .name("size")
-                                .description("Size of tic-tac-toe board.")
-                                .kind(ApplicationCommandOptionType::Integer)
-                                .min_int_value(2)
-                                .max_int_value(5)
-                        })
-                })
-                .create_application_command(|command| {
-                    command.name("error").description("Test error.")
-                })
-        })
-        .await
-        .unwrap();
-
-        info!(
-            "Registered commands: {:?}",
-            new_commands.into_iter().map(|c| c.name).collect::<Vec<_>>()
-        );
     }
 }
 
 #[tokio::main]
 async fn main() {
     // Init logging
-    tracing_subscriber::fmt::init();
+    logging::init();
 
     // Get token from .env
     let token = dotenvy::var("DISCORD_TOKEN").expect("Unable to find discord token");
@@ -161,23 +67,51 @@ async fn main() {
         .await
         .expect("Error creating client");
 
+    // Games are saved here between restarts, if set
+    let data_dir = dotenvy::var("DATA_DIR").ok().map(PathBuf::from);
+
     // Setup persistent data stores
     {
         let mut data = client.data.write().await;
         data.insert::<ShardManagerContainer>(client.shard_manager.clone());
-        data.insert::<MinesweeperGames>(HashMap::default());
-        data.insert::<TictactoeGames>(HashMap::default());
+        data.insert::<GameStore<MinesweeperGame>>(load_games(data_dir.as_deref()));
+        let tictactoe_games = load_games(data_dir.as_deref());
+        let http = client.http.clone();
+        resume_timers(&tictactoe_games, |id, token| {
+            InteractionResponder::new(http.clone(), InteractionId::new(id.0), token.to_string())
+        });
+        data.insert::<GameStore<TictactoeGame>>(tictactoe_games);
+        data.insert::<RecordsContainer>(Arc::new(load_records(data_dir.as_deref())));
+        data.insert::<MetricsContainer>(Arc::new(Metrics::new()));
+        data.insert::<RateLimiterContainer>(Arc::new(RateLimiter::new(Limits::from_env())));
+        data.insert::<HealthContainer>(Arc::new(Health::new(data_dir.clone())));
+        if let Some(reporter) = ErrorReporter::from_env() {
+            data.insert::<ErrorReporterContainer>(reporter);
+        }
+    }
+
+    maintain::<MinesweeperGame>(client.data.clone(), data_dir.clone());
+    maintain::<TictactoeGame>(client.data.clone(), data_dir.clone());
+    if let Some(dir) = data_dir.clone() {
+        records::maintain(client.data.clone(), dir);
+    }
+    if let Some(addr) = metrics::addr_from_env() {
+        metrics::serve(client.data.clone(), addr);
+    }
+    if let Some(addr) = health::addr_from_env() {
+        health::serve(client.data.clone(), addr);
     }
 
     let shard_manager = client.shard_manager.clone();
+    let data = client.data.clone();
+    let http = client.http.clone();
 
-    // Ctrl+C Handler
+    // Ctrl+C and SIGTERM handler
     tokio::spawn(async move {
-        tokio::signal::ctrl_c()
-            .await
-            .expect("Error registering Ctrl+C handler");
-        info!("Recieved Ctrl+C, shutting down");
-        shard_manager.lock().await.shutdown_all().await;
+        shutdown::signal().await;
+        info!("Recieved shutdown signal, shutting down");
+        shutdown::finish_games(&data, &http, data_dir.as_deref()).await;
+        shard_manager.shutdown_all().await;
     });
 
     // Start client
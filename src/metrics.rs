@@ -0,0 +1,271 @@
+//! Counters and timings for Prometheus, served over HTTP on a local port.
+
+use std::net::SocketAddr;
+use std::num::ParseIntError;
+use std::sync::Arc;
+use std::time::Duration;
+
+use hyper::{Body, Method, Request, Response};
+use prometheus::{
+    Encoder, GaugeVec, HistogramOpts, HistogramVec, IntCounterVec, IntGaugeVec, Opts, Registry,
+    TextEncoder,
+};
+use serenity::prelude::*;
+
+use crate::commands::minesweeper::MinesweeperGame;
+use crate::commands::tictactoe::TictactoeGame;
+use crate::games::MoveError;
+use crate::http::{self, not_found};
+use crate::store::{Game, GameStore};
+use crate::{Error, ShardManagerContainer};
+
+/// Where the metrics are served when `METRICS_ADDR` isn't set.
+const DEFAULT_ADDR: &str = "127.0.0.1:9090";
+
+pub struct Metrics {
+    registry: Registry,
+    commands: IntCounterVec,
+    components: IntCounterVec,
+    latency: HistogramVec,
+    errors: IntCounterVec,
+    active_games: IntGaugeVec,
+    shard_latency: GaugeVec,
+}
+
+impl Metrics {
+    pub fn new() -> Metrics {
+        let commands = IntCounterVec::new(
+            Opts::new("slaash_commands_total", "Commands invoked."),
+            &["command"],
+        )
+        .unwrap();
+        let components = IntCounterVec::new(
+            Opts::new(
+                "slaash_component_interactions_total",
+                "Buttons pressed, by the command they belong to.",
+            ),
+            &["component"],
+        )
+        .unwrap();
+        let latency = HistogramVec::new(
+            HistogramOpts::new(
+                "slaash_handler_duration_seconds",
+                "Time taken to handle an interaction, including responding to it.",
+            ),
+            &["interaction", "name"],
+        )
+        .unwrap();
+        let errors = IntCounterVec::new(
+            Opts::new("slaash_errors_total", "Interactions that failed."),
+            &["interaction", "kind"],
+        )
+        .unwrap();
+        let active_games = IntGaugeVec::new(
+            Opts::new("slaash_active_games", "Games still being played."),
+            &["game"],
+        )
+        .unwrap();
+        let shard_latency = GaugeVec::new(
+            Opts::new(
+                "slaash_shard_latency_seconds",
+                "Time between a gateway heartbeat and its acknowledgement.",
+            ),
+            &["shard"],
+        )
+        .unwrap();
+
+        let registry = Registry::new();
+        registry.register(Box::new(commands.clone())).unwrap();
+        registry.register(Box::new(components.clone())).unwrap();
+        registry.register(Box::new(latency.clone())).unwrap();
+        registry.register(Box::new(errors.clone())).unwrap();
+        registry.register(Box::new(active_games.clone())).unwrap();
+        registry.register(Box::new(shard_latency.clone())).unwrap();
+
+        Metrics {
+            registry,
+            commands,
+            components,
+            latency,
+            errors,
+            active_games,
+            shard_latency,
+        }
+    }
+
+    /// Records a handled interaction. `interaction` is `command`,
+    /// `component` or `modal`, and `name` is the command it belongs to.
+    /// Modals are counted with components.
+    pub fn observe(
+        &self,
+        interaction: &str,
+        name: &str,
+        elapsed: Duration,
+        result: &Result<(), Error>,
+    ) {
+        match interaction {
+            "command" => self.commands.with_label_values(&[name]).inc(),
+            _ => self.components.with_label_values(&[name]).inc(),
+        }
+        self.latency
+            .with_label_values(&[interaction, name])
+            .observe(elapsed.as_secs_f64());
+        if let Err(err) = result {
+            self.errors
+                .with_label_values(&[interaction, error_kind(err)])
+                .inc();
+        }
+    }
+
+    fn count_games<T: Game>(&self, data: &TypeMap) {
+        if let Some(games) = data.get::<GameStore<T>>() {
+            self.active_games
+                .with_label_values(&[T::NAME])
+                .set(games.active() as i64);
+        }
+    }
+
+    /// Every metric in Prometheus' text format, with the gauges read from
+    /// `data` first.
+    pub async fn render(&self, data: &RwLock<TypeMap>) -> String {
+        let shard_manager = {
+            let data = data.read().await;
+            self.count_games::<MinesweeperGame>(&data);
+            self.count_games::<TictactoeGame>(&data);
+            data.get::<ShardManagerContainer>().cloned()
+        };
+
+        if let Some(shard_manager) = shard_manager {
+            let runners = shard_manager.runners.clone();
+            self.shard_latency.reset();
+            for (id, runner) in runners.lock().await.iter() {
+                if let Some(latency) = runner.latency {
+                    self.shard_latency
+                        .with_label_values(&[&id.0.to_string()])
+                        .set(latency.as_secs_f64());
+                }
+            }
+        }
+
+        let mut text = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut text)
+            .unwrap();
+        String::from_utf8(text).unwrap()
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Metrics {
+        Metrics::new()
+    }
+}
+
+pub struct MetricsContainer;
+
+impl TypeMapKey for MetricsContainer {
+    type Value = Arc<Metrics>;
+}
+
+/// What went wrong, broadly, for the error counter's `kind` label.
+fn error_kind(err: &Error) -> &'static str {
+    if err.is::<serenity::Error>() {
+        "discord"
+    } else if err.is::<MoveError>() {
+        "move"
+    } else if err.is::<ParseIntError>() {
+        "parse"
+    } else {
+        "other"
+    }
+}
+
+/// Records a handled interaction, if metrics are being collected.
+pub async fn record_interaction(
+    data: &RwLock<TypeMap>,
+    interaction: &str,
+    name: &str,
+    elapsed: Duration,
+    result: &Result<(), Error>,
+) {
+    if let Some(metrics) = data.read().await.get::<MetricsContainer>() {
+        metrics.observe(interaction, name, elapsed, result);
+    }
+}
+
+/// The address to serve metrics on, from `METRICS_ADDR` or `DEFAULT_ADDR`.
+pub fn addr_from_env() -> Option<SocketAddr> {
+    http::addr_from_env("METRICS_ADDR").or_else(|| DEFAULT_ADDR.parse().ok())
+}
+
+async fn respond(data: Arc<RwLock<TypeMap>>, request: Request<Body>) -> Response<Body> {
+    let metrics = data.read().await.get::<MetricsContainer>().cloned();
+    match (request.method(), request.uri().path(), metrics) {
+        (&Method::GET, "/metrics", Some(metrics)) => Response::builder()
+            .header("Content-Type", TextEncoder::new().format_type())
+            .body(Body::from(metrics.render(&data).await))
+            .unwrap(),
+        _ => not_found(),
+    }
+}
+
+/// Serves `/metrics` on `addr` in the background.
+pub fn serve(data: Arc<RwLock<TypeMap>>, addr: SocketAddr) {
+    http::serve("metrics", data, addr, respond);
+}
+
+#[cfg(test)]
+mod tests {
+    use hyper::StatusCode;
+    use serenity::model::id::UserId;
+
+    use super::*;
+    use crate::commands::tictactoe::TictactoeGames;
+    use crate::store::GameId;
+
+    async fn get(data: &Arc<RwLock<TypeMap>>, path: &str) -> (StatusCode, String) {
+        let request = Request::get(path).body(Body::empty()).unwrap();
+        let response = respond(data.clone(), request).await;
+        let status = response.status();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn reports_interactions_and_games() {
+        let metrics = Arc::new(Metrics::new());
+        let games = TictactoeGames::default();
+        games.insert(GameId(1), TictactoeGame::new(UserId::new(10), 3));
+        let mut data = TypeMap::new();
+        data.insert::<MetricsContainer>(metrics.clone());
+        data.insert::<TictactoeGames>(games);
+        let data = Arc::new(RwLock::new(data));
+
+        metrics.observe("command", "tictactoe", Duration::from_millis(20), &Ok(()));
+        metrics.observe(
+            "component",
+            "tictactoe",
+            Duration::from_millis(5),
+            &Err(MoveError::NotYourTurn.into()),
+        );
+
+        let (status, text) = get(&data, "/metrics").await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(text.contains("slaash_commands_total{command=\"tictactoe\"} 1\n"));
+        assert!(text.contains("slaash_component_interactions_total{component=\"tictactoe\"} 1\n"));
+        assert!(text.contains(
+            "slaash_handler_duration_seconds_count{interaction=\"command\",name=\"tictactoe\"} 1\n"
+        ));
+        assert!(text.contains("slaash_errors_total{interaction=\"component\",kind=\"move\"} 1\n"));
+        assert!(text.contains("slaash_active_games{game=\"tictactoe\"} 1\n"));
+    }
+
+    #[tokio::test]
+    async fn only_serves_metrics() {
+        let mut data = TypeMap::new();
+        data.insert::<MetricsContainer>(Arc::new(Metrics::new()));
+        let data = Arc::new(RwLock::new(data));
+
+        assert_eq!(get(&data, "/").await.0, StatusCode::NOT_FOUND);
+    }
+}
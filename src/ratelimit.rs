@@ -0,0 +1,394 @@
+//! Keeping a single user from flooding the bot with commands, button presses
+//! or games.
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use dashmap::DashMap;
+use serenity::builder::{CreateInteractionResponse, CreateInteractionResponseMessage};
+use serenity::model::id::{GuildId, UserId};
+use serenity::prelude::*;
+use tokio::time::Instant;
+
+use tracing::warn;
+
+use crate::commands::minesweeper::MinesweeperGames;
+use crate::commands::responder::Responder;
+use crate::commands::tictactoe::TictactoeGames;
+use crate::locale::{tr, Locale};
+use crate::Error;
+
+/// Buckets are only cleared out once there are this many, so most checks
+/// don't have to look at every bucket.
+const PRUNE_AT: usize = 10_000;
+
+/// How many interactions can be made in a row, and how long it takes to be
+/// able to make that many again. Written as `burst/seconds`, like `5/10`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Rate {
+    pub burst: u32,
+    pub per: Duration,
+}
+
+impl Rate {
+    /// Time for a single interaction to be allowed again.
+    fn refill(&self) -> Duration {
+        self.per / self.burst
+    }
+}
+
+impl fmt::Display for Rate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.burst, self.per.as_secs())
+    }
+}
+
+impl FromStr for Rate {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Rate, Error> {
+        let (burst, seconds) = s.split_once('/').ok_or("Expected burst/seconds")?;
+        let rate = Rate {
+            burst: burst.trim().parse()?,
+            per: Duration::from_secs(seconds.trim().parse()?),
+        };
+        if rate.burst == 0 || rate.per.is_zero() {
+            return Err("Rate can't be zero".into());
+        }
+        Ok(rate)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Limits {
+    /// Per user, guild and command.
+    pub commands: Rate,
+    /// Per user, guild and the command the buttons belong to.
+    pub buttons: Rate,
+    /// Games a user can be playing at once.
+    pub games_per_user: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Limits {
+        Limits {
+            commands: Rate {
+                burst: 5,
+                per: Duration::from_secs(10),
+            },
+            buttons: Rate {
+                burst: 10,
+                per: Duration::from_secs(5),
+            },
+            games_per_user: 3,
+        }
+    }
+}
+
+impl Limits {
+    /// Reads `COMMAND_RATE`, `BUTTON_RATE` and `MAX_GAMES_PER_USER`, keeping
+    /// the default for any that aren't set or can't be read.
+    pub fn from_env() -> Limits {
+        fn var<T: FromStr>(name: &str, default: T) -> T
+        where
+            T::Err: fmt::Display,
+        {
+            match dotenvy::var(name) {
+                Ok(value) => value.parse().unwrap_or_else(|err| {
+                    warn!("Ignoring {} '{}': {}", name, value, err);
+                    default
+                }),
+                Err(_) => default,
+            }
+        }
+
+        let default = Limits::default();
+        Limits {
+            commands: var("COMMAND_RATE", default.commands),
+            buttons: var("BUTTON_RATE", default.buttons),
+            games_per_user: var("MAX_GAMES_PER_USER", default.games_per_user),
+        }
+    }
+}
+
+/// A token bucket: it holds up to `burst` interactions, and one is added
+/// back every `Rate::refill`.
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+    /// The rate the bucket fills at, which depends on the kind of
+    /// interaction it is for.
+    rate: Rate,
+}
+
+impl Bucket {
+    fn refilled(&self, now: Instant) -> f64 {
+        let rate = self.rate;
+        let refilled = now.duration_since(self.updated).as_secs_f64() / rate.refill().as_secs_f64();
+        (self.tokens + refilled).min(rate.burst as f64)
+    }
+
+    fn is_full(&self, now: Instant) -> bool {
+        self.refilled(now) >= self.rate.burst as f64
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct Key {
+    user: UserId,
+    guild: Option<GuildId>,
+    interaction: &'static str,
+    name: String,
+}
+
+pub struct RateLimiter {
+    limits: Limits,
+    buckets: DashMap<Key, Bucket>,
+}
+
+impl RateLimiter {
+    pub fn new(limits: Limits) -> RateLimiter {
+        RateLimiter {
+            limits,
+            buckets: DashMap::new(),
+        }
+    }
+
+    pub fn limits(&self) -> Limits {
+        self.limits
+    }
+
+    /// Takes an interaction from `user`'s bucket for `name`, or returns how
+    /// long until there is one. `interaction` is `command`, `component` or
+    /// `modal`; the last two share the button rate.
+    pub fn check(
+        &self,
+        interaction: &'static str,
+        name: &str,
+        user: UserId,
+        guild: Option<GuildId>,
+    ) -> Result<(), Duration> {
+        let rate = match interaction {
+            "command" => self.limits.commands,
+            _ => self.limits.buttons,
+        };
+        let now = Instant::now();
+        if self.buckets.len() >= PRUNE_AT {
+            self.buckets.retain(|_, bucket| !bucket.is_full(now));
+        }
+
+        let key = Key {
+            user,
+            guild,
+            interaction,
+            name: name.to_string(),
+        };
+        let mut bucket = self.buckets.entry(key).or_insert(Bucket {
+            tokens: rate.burst as f64,
+            updated: now,
+            rate,
+        });
+        let tokens = bucket.refilled(now);
+        bucket.updated = now;
+        if tokens >= 1.0 {
+            bucket.tokens = tokens - 1.0;
+            Ok(())
+        } else {
+            bucket.tokens = tokens;
+            Err(rate.refill().mul_f64(1.0 - tokens))
+        }
+    }
+}
+
+pub struct RateLimiterContainer;
+
+impl TypeMapKey for RateLimiterContainer {
+    type Value = Arc<RateLimiter>;
+}
+
+/// How long `user` has to wait before `name` can be used again, if they are
+/// going too fast.
+pub async fn throttle(
+    data: &RwLock<TypeMap>,
+    interaction: &'static str,
+    name: &str,
+    user: UserId,
+    guild: Option<GuildId>,
+) -> Option<Duration> {
+    let data = data.read().await;
+    let limiter = data.get::<RateLimiterContainer>()?;
+    limiter.check(interaction, name, user, guild).err()
+}
+
+/// Answers an interaction that came too soon after the last ones.
+pub async fn slow_down(
+    responder: &impl Responder,
+    wait: Duration,
+    locale: Locale,
+) -> Result<(), Error> {
+    let seconds = wait.as_secs_f64().ceil().max(1.0) as u64;
+    responder
+        .create_interaction_response(CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new()
+                .content(tr!(locale, "slow-down", seconds = seconds))
+                .ephemeral(true),
+        ))
+        .await
+}
+
+/// Whether `user` is already playing as many games as they are allowed.
+pub async fn has_too_many_games(data: &RwLock<TypeMap>, user: UserId) -> bool {
+    let (limit, minesweeper, tictactoe) = {
+        let data = data.read().await;
+        let limiter = match data.get::<RateLimiterContainer>() {
+            Some(limiter) => limiter,
+            None => return false,
+        };
+        (
+            limiter.limits().games_per_user,
+            data.get::<MinesweeperGames>().cloned(),
+            data.get::<TictactoeGames>().cloned(),
+        )
+    };
+    let playing = minesweeper.map_or(0, |games| games.active_for(user))
+        + tictactoe.map_or(0, |games| games.active_for(user));
+    playing >= limit
+}
+
+/// Answers a command that would start a game while the user has too many.
+pub async fn refuse_another_game(responder: &impl Responder, locale: Locale) -> Result<(), Error> {
+    responder
+        .create_interaction_response(CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new()
+                .content(tr!(locale, "too-many-games"))
+                .ephemeral(true),
+        ))
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::minesweeper::MinesweeperGame;
+    use crate::store::GameId;
+
+    const USER: UserId = UserId::new(10);
+
+    fn limiter() -> RateLimiter {
+        RateLimiter::new(Limits {
+            commands: Rate {
+                burst: 2,
+                per: Duration::from_secs(10),
+            },
+            ..Limits::default()
+        })
+    }
+
+    #[test]
+    fn reads_rates() {
+        assert_eq!(
+            "3/20".parse::<Rate>().unwrap(),
+            Rate {
+                burst: 3,
+                per: Duration::from_secs(20)
+            }
+        );
+        assert!("3".parse::<Rate>().is_err());
+        assert!("0/20".parse::<Rate>().is_err());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn buckets_refill() {
+        let limiter = limiter();
+
+        assert_eq!(limiter.check("command", "ping", USER, None), Ok(()));
+        assert_eq!(limiter.check("command", "ping", USER, None), Ok(()));
+        assert_eq!(
+            limiter.check("command", "ping", USER, None),
+            Err(Duration::from_secs(5))
+        );
+
+        tokio::time::advance(Duration::from_secs(3)).await;
+        assert_eq!(
+            limiter.check("command", "ping", USER, None),
+            Err(Duration::from_secs(2))
+        );
+        tokio::time::advance(Duration::from_secs(2)).await;
+        assert_eq!(limiter.check("command", "ping", USER, None), Ok(()));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn buckets_are_separate() {
+        let limiter = limiter();
+        for _ in 0..2 {
+            limiter.check("command", "ping", USER, None).unwrap();
+        }
+
+        assert!(limiter.check("command", "ping", USER, None).is_err());
+        assert_eq!(limiter.check("command", "blackjack", USER, None), Ok(()));
+        assert_eq!(limiter.check("component", "ping", USER, None), Ok(()));
+        assert_eq!(
+            limiter.check("command", "ping", USER, Some(GuildId::new(1))),
+            Ok(())
+        );
+        assert_eq!(
+            limiter.check("command", "ping", UserId::new(11), None),
+            Ok(())
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn prunes_only_full_buckets() {
+        let limiter = limiter();
+        for _ in 0..10 {
+            limiter.check("component", "ping", USER, None).unwrap();
+        }
+        tokio::time::advance(Duration::from_secs(2)).await;
+        limiter.check("component", "ping", USER, None).unwrap();
+
+        // Enough drained buckets that the next check prunes
+        let now = Instant::now();
+        for user in 1000..1000 + PRUNE_AT as u64 {
+            let key = Key {
+                user: UserId::new(user),
+                guild: None,
+                interaction: "command",
+                name: String::from("ping"),
+            };
+            let rate = limiter.limits().commands;
+            let bucket = Bucket {
+                tokens: 0.0,
+                updated: now,
+                rate,
+            };
+            limiter.buckets.insert(key, bucket);
+        }
+        limiter.check("command", "ping", USER, None).unwrap();
+
+        // Three presses were left, which is more than a command bucket holds
+        for _ in 0..3 {
+            limiter.check("component", "ping", USER, None).unwrap();
+        }
+        assert!(limiter.check("component", "ping", USER, None).is_err());
+    }
+
+    #[tokio::test]
+    async fn caps_games_per_user() {
+        let games = MinesweeperGames::default();
+        let mut data = TypeMap::new();
+        data.insert::<RateLimiterContainer>(Arc::new(RateLimiter::new(Limits {
+            games_per_user: 2,
+            ..Limits::default()
+        })));
+        data.insert::<MinesweeperGames>(games.clone());
+        let data = RwLock::new(data);
+
+        games.insert(GameId(1), MinesweeperGame::new(USER, 5));
+        assert!(!has_too_many_games(&data, USER).await);
+        games.insert(GameId(2), MinesweeperGame::new(USER, 5));
+        assert!(has_too_many_games(&data, USER).await);
+        assert!(!has_too_many_games(&data, UserId::new(11)).await);
+    }
+}
@@ -0,0 +1,195 @@
+//! Players' best times, saved alongside the games so they outlast restarts.
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serenity::model::id::UserId;
+use serenity::prelude::*;
+
+use tracing::error;
+
+use crate::store::{MAINTENANCE_INTERVAL, SAVING};
+use crate::Error;
+
+/// How a finished time compares with the player's best before it.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Best {
+    /// The first time recorded for what was played.
+    First,
+    /// A new best, faster than the old one by this much.
+    Improved(Duration),
+    /// Slower than the best, which still stands.
+    Kept(Duration),
+}
+
+/// Each player's best time at everything they have finished, named by the
+/// game, such as a minesweeper level.
+#[derive(Default)]
+pub struct Records {
+    bests: Mutex<HashMap<UserId, HashMap<String, Duration>>>,
+    /// Whether there are bests that haven't been saved yet.
+    changed: AtomicBool,
+}
+
+impl Records {
+    /// Compares `time` with `user`'s best at `name`, keeping it if it is
+    /// faster.
+    pub fn submit(&self, user: UserId, name: &str, time: Duration) -> Best {
+        let mut bests = self.bests.lock().unwrap();
+        let best = bests.entry(user).or_default().entry(name.to_string());
+        let result = match best {
+            Entry::Occupied(mut best) => {
+                let previous = *best.get();
+                if time >= previous {
+                    return Best::Kept(previous);
+                }
+                best.insert(time);
+                Best::Improved(previous - time)
+            }
+            Entry::Vacant(best) => {
+                best.insert(time);
+                Best::First
+            }
+        };
+        self.changed.store(true, Ordering::Relaxed);
+        result
+    }
+
+    pub fn path(dir: &Path) -> PathBuf {
+        dir.join("records.json")
+    }
+
+    pub fn to_json(&self) -> Result<Vec<u8>, Error> {
+        Ok(serde_json::to_vec(&*self.bests.lock().unwrap())?)
+    }
+
+    pub fn from_json(json: &[u8]) -> Result<Records, Error> {
+        Ok(Records {
+            bests: Mutex::new(serde_json::from_slice(json)?),
+            changed: AtomicBool::new(false),
+        })
+    }
+
+    /// Loads the records saved in `dir`, if there are any.
+    pub fn load(dir: &Path) -> Result<Records, Error> {
+        match std::fs::read(Self::path(dir)) {
+            Ok(json) => Records::from_json(&json),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Records::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+pub struct RecordsContainer;
+
+impl TypeMapKey for RecordsContainer {
+    type Value = Arc<Records>;
+}
+
+/// Saves the records in `data` to `dir` if they changed since the last save,
+/// replacing it.
+pub async fn save(data: &RwLock<TypeMap>, dir: &Path) -> Result<(), Error> {
+    let records = match data.read().await.get::<RecordsContainer>() {
+        Some(records) => records.clone(),
+        None => return Ok(()),
+    };
+    if !records.changed.swap(false, Ordering::Relaxed) {
+        return Ok(());
+    }
+    let json = records.to_json()?;
+
+    let path = Records::path(dir);
+    let temp = path.with_extension("json.tmp");
+    let _saving = SAVING.lock().await;
+    let written = async {
+        tokio::fs::create_dir_all(dir).await?;
+        tokio::fs::write(&temp, json).await?;
+        tokio::fs::rename(&temp, &path).await
+    };
+    if let Err(err) = written.await {
+        // Try again next time
+        records.changed.store(true, Ordering::Relaxed);
+        return Err(err.into());
+    }
+    Ok(())
+}
+
+/// Periodically saves the records to `dir`.
+pub fn maintain(data: Arc<RwLock<TypeMap>>, dir: PathBuf) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(MAINTENANCE_INTERVAL);
+        loop {
+            interval.tick().await;
+
+            if let Err(err) = save(&data, &dir).await {
+                error!("Failed to save records: {}", err);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAYER: UserId = UserId::new(10);
+
+    #[test]
+    fn keeps_the_fastest_time() {
+        let records = Records::default();
+        let time = Duration::from_millis;
+
+        assert_eq!(records.submit(PLAYER, "5x5", time(9000)), Best::First);
+        assert_eq!(
+            records.submit(PLAYER, "5x5", time(7800)),
+            Best::Improved(time(1200))
+        );
+        assert_eq!(
+            records.submit(PLAYER, "5x5", time(8000)),
+            Best::Kept(time(7800))
+        );
+        assert_eq!(records.submit(PLAYER, "4x4", time(8000)), Best::First);
+        assert_eq!(
+            records.submit(UserId::new(20), "5x5", time(9000)),
+            Best::First
+        );
+    }
+
+    #[test]
+    fn survives_a_round_trip() {
+        let records = Records::default();
+        records.submit(PLAYER, "5x5", Duration::from_millis(1234));
+
+        let loaded = Records::from_json(&records.to_json().unwrap()).unwrap();
+        assert_eq!(
+            loaded.submit(PLAYER, "5x5", Duration::from_secs(2)),
+            Best::Kept(Duration::from_millis(1234))
+        );
+    }
+
+    #[tokio::test]
+    async fn saves_only_when_changed() {
+        let dir = std::env::temp_dir().join(format!("slaash-records-{}", std::process::id()));
+        let mut data = TypeMap::new();
+        data.insert::<RecordsContainer>(Arc::new(Records::default()));
+        let data = RwLock::new(data);
+
+        save(&data, &dir).await.unwrap();
+        assert!(!Records::path(&dir).exists());
+
+        let records = data.read().await.get::<RecordsContainer>().unwrap().clone();
+        records.submit(PLAYER, "5x5", Duration::from_secs(3));
+        save(&data, &dir).await.unwrap();
+        let loaded = Records::load(&dir).unwrap();
+        assert_eq!(
+            loaded.submit(PLAYER, "5x5", Duration::from_secs(4)),
+            Best::Kept(Duration::from_secs(3))
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
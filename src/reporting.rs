@@ -0,0 +1,237 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use rand::Rng;
+
+use serenity::builder::{CreateEmbed, CreateEmbedFooter, CreateMessage};
+use serenity::http::Http;
+use serenity::model::id::{ChannelId, GuildId, UserId};
+use serenity::model::Colour;
+use serenity::prelude::*;
+
+use tracing::{error, warn};
+
+/// How long an identical error is suppressed after being posted.
+const DEDUP_WINDOW: Duration = Duration::from_secs(300);
+/// Window over which `RATE_LIMIT` reports may be posted.
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+const RATE_LIMIT: usize = 5;
+
+/// Details about a failed interaction, posted to the admin log channel.
+pub struct ErrorReport {
+    pub kind: &'static str,
+    pub name: String,
+    pub user: UserId,
+    pub guild: Option<GuildId>,
+    pub custom_id: Option<String>,
+    pub error: String,
+    pub correlation_id: String,
+}
+
+impl ErrorReport {
+    fn fingerprint(&self) -> String {
+        format!("{}:{}:{}", self.kind, self.name, self.error)
+    }
+}
+
+/// Reports that weren't posted, summed up in the next one that is.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+struct Suppressed {
+    /// Identical reports within the dedup window.
+    repeats: usize,
+    /// Reports of any kind dropped by the rate limit.
+    throttled: usize,
+}
+
+/// Posts internal errors to a configured channel, skipping duplicates and
+/// throttling bursts so a crash loop doesn't flood the channel.
+pub struct ErrorReporter {
+    channel: ChannelId,
+    recent: HashMap<String, (Instant, usize)>,
+    sent: Vec<Instant>,
+    /// Reports dropped by the rate limit since the last one was posted.
+    throttled: usize,
+}
+
+impl ErrorReporter {
+    pub fn new(channel: ChannelId) -> ErrorReporter {
+        ErrorReporter {
+            channel,
+            recent: HashMap::new(),
+            sent: Vec::new(),
+            throttled: 0,
+        }
+    }
+
+    /// Reads the channel from `ERROR_CHANNEL_ID`, if one is configured.
+    pub fn from_env() -> Option<ErrorReporter> {
+        let id = dotenvy::var("ERROR_CHANNEL_ID").ok()?;
+        match id.parse::<u64>() {
+            Ok(id) => Some(ErrorReporter::new(ChannelId::new(id))),
+            Err(err) => {
+                warn!("Invalid ERROR_CHANNEL_ID '{}': {}", id, err);
+                None
+            }
+        }
+    }
+
+    /// Decides whether a report should be posted now. Returns the reports
+    /// suppressed since the last one was posted.
+    fn admit(&mut self, report: &ErrorReport, now: Instant) -> Option<Suppressed> {
+        self.sent
+            .retain(|&sent| now.duration_since(sent) < RATE_WINDOW);
+
+        let fingerprint = report.fingerprint();
+        let repeats = match self.recent.get_mut(&fingerprint) {
+            Some((last, repeats)) if now.duration_since(*last) < DEDUP_WINDOW => {
+                *repeats += 1;
+                return None;
+            }
+            Some((_, repeats)) => *repeats,
+            None => 0,
+        };
+        if self.sent.len() >= RATE_LIMIT {
+            self.throttled += 1;
+            return None;
+        }
+
+        self.recent
+            .retain(|_, (last, _)| now.duration_since(*last) < DEDUP_WINDOW);
+        self.recent.insert(fingerprint, (now, 0));
+        self.sent.push(now);
+        Some(Suppressed {
+            repeats,
+            throttled: std::mem::take(&mut self.throttled),
+        })
+    }
+}
+
+pub struct ErrorReporterContainer;
+
+impl TypeMapKey for ErrorReporterContainer {
+    type Value = ErrorReporter;
+}
+
+/// Short random id shown to the user and attached to the report so the two
+/// can be matched up.
+pub fn correlation_id() -> String {
+    format!("{:08x}", rand::thread_rng().gen::<u32>())
+}
+
+pub async fn report_error(data: &RwLock<TypeMap>, http: &Http, report: ErrorReport) {
+    let (channel, suppressed) = {
+        let mut data = data.write().await;
+        let reporter = match data.get_mut::<ErrorReporterContainer>() {
+            Some(reporter) => reporter,
+            None => return,
+        };
+        match reporter.admit(&report, Instant::now()) {
+            Some(suppressed) => (reporter.channel, suppressed),
+            None => return,
+        }
+    };
+
+    let mut embed = CreateEmbed::new()
+        .title(format!("{} '{}' failed", report.kind, report.name))
+        .description(format!("```{}```", report.error))
+        .colour(Colour::RED)
+        .field("User", format!("<@{}>", report.user), true)
+        .field(
+            "Guild",
+            report
+                .guild
+                .map_or_else(|| String::from("DM"), |guild| guild.to_string()),
+            true,
+        );
+    if let Some(custom_id) = &report.custom_id {
+        embed = embed.field("Custom ID", format!("`{}`", custom_id), true);
+    }
+    if suppressed.repeats > 0 {
+        let repeats = format!("{} since last report", suppressed.repeats);
+        embed = embed.field("Repeats", repeats, true);
+    }
+    if suppressed.throttled > 0 {
+        let throttled = format!("{} other errors since last report", suppressed.throttled);
+        embed = embed.field("Rate limited", throttled, true);
+    }
+    let embed = embed.footer(CreateEmbedFooter::new(format!(
+        "Error ID: {}",
+        report.correlation_id
+    )));
+
+    let result = channel
+        .send_message(http, CreateMessage::new().embed(embed))
+        .await;
+
+    if let Err(err) = result {
+        error!("Failed to report error {}: {}", report.correlation_id, err);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(error: &str) -> ErrorReport {
+        ErrorReport {
+            kind: "Command",
+            name: String::from("minesweeper"),
+            user: UserId::new(10),
+            guild: None,
+            custom_id: None,
+            error: error.to_string(),
+            correlation_id: correlation_id(),
+        }
+    }
+
+    fn suppressed(repeats: usize, throttled: usize) -> Option<Suppressed> {
+        Some(Suppressed { repeats, throttled })
+    }
+
+    #[test]
+    fn skips_repeats_within_the_window() {
+        let mut reporter = ErrorReporter::new(ChannelId::new(1));
+        let now = Instant::now();
+
+        assert_eq!(reporter.admit(&report("boom"), now), suppressed(0, 0));
+        assert_eq!(reporter.admit(&report("boom"), now), None);
+        let later = now + DEDUP_WINDOW - Duration::from_secs(1);
+        assert_eq!(reporter.admit(&report("boom"), later), None);
+        assert_eq!(reporter.admit(&report("bang"), later), suppressed(0, 0));
+    }
+
+    #[test]
+    fn posts_repeats_again_after_the_window() {
+        let mut reporter = ErrorReporter::new(ChannelId::new(1));
+        let now = Instant::now();
+
+        reporter.admit(&report("boom"), now);
+        reporter.admit(&report("boom"), now + Duration::from_secs(1));
+        reporter.admit(&report("boom"), now + Duration::from_secs(2));
+        assert_eq!(
+            reporter.admit(&report("boom"), now + DEDUP_WINDOW),
+            suppressed(2, 0)
+        );
+        assert_eq!(
+            reporter.admit(&report("boom"), now + DEDUP_WINDOW * 2),
+            suppressed(0, 0)
+        );
+    }
+
+    #[test]
+    fn counts_reports_dropped_by_the_rate_limit() {
+        let mut reporter = ErrorReporter::new(ChannelId::new(1));
+        let now = Instant::now();
+
+        for i in 0..RATE_LIMIT {
+            let admitted = reporter.admit(&report(&i.to_string()), now);
+            assert_eq!(admitted, suppressed(0, 0));
+        }
+        assert_eq!(reporter.admit(&report("late"), now), None);
+        assert_eq!(reporter.admit(&report("later"), now), None);
+
+        let later = now + RATE_WINDOW;
+        assert_eq!(reporter.admit(&report("latest"), later), suppressed(0, 2));
+        assert_eq!(reporter.admit(&report("last"), later), suppressed(0, 0));
+    }
+}
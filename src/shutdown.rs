@@ -0,0 +1,125 @@
+//! Stopping the bot without losing the games being played.
+
+use std::path::Path;
+use std::time::Duration;
+
+use serenity::builder::{CreateInteractionResponse, CreateInteractionResponseMessage};
+use serenity::http::Http;
+use serenity::json::json;
+use serenity::prelude::*;
+
+use tracing::{error, info, warn};
+
+use crate::commands::minesweeper::MinesweeperGame;
+use crate::commands::responder::Responder;
+use crate::commands::tictactoe::TictactoeGame;
+use crate::locale::{tr, Locale};
+use crate::records;
+use crate::store::{save, Game, GameStore};
+use crate::Error;
+
+/// How long games have to be saved and their players told before the bot
+/// stops anyway.
+pub const GRACE_PERIOD: Duration = Duration::from_secs(10);
+
+/// Whether the bot is shutting down, in which case no new games are started.
+pub struct ShuttingDown;
+
+impl TypeMapKey for ShuttingDown {
+    type Value = bool;
+}
+
+pub async fn is_shutting_down(data: &RwLock<TypeMap>) -> bool {
+    data.read()
+        .await
+        .get::<ShuttingDown>()
+        .copied()
+        .unwrap_or_default()
+}
+
+/// Answers a command that would start a game while shutting down.
+pub async fn refuse_game(responder: &impl Responder, locale: Locale) -> Result<(), Error> {
+    responder
+        .create_interaction_response(CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new()
+                .content(tr!(locale, "restarting"))
+                .ephemeral(true),
+        ))
+        .await
+}
+
+/// Waits for Ctrl+C or, on Unix, SIGTERM.
+pub async fn signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut terminate =
+            signal(SignalKind::terminate()).expect("Error registering SIGTERM handler");
+        tokio::select! {
+            result = tokio::signal::ctrl_c() => result.expect("Error registering Ctrl+C handler"),
+            _ = terminate.recv() => {}
+        }
+    }
+    #[cfg(not(unix))]
+    tokio::signal::ctrl_c()
+        .await
+        .expect("Error registering Ctrl+C handler");
+}
+
+/// Saves the `T` games to `data_dir`, if set, and tells the players of
+/// those still running that the bot is restarting: that their game will
+/// resume if it was saved, or that it has ended if it wasn't.
+async fn finish<T: Game>(data: &RwLock<TypeMap>, http: &Http, data_dir: Option<&Path>) {
+    let saved = match data_dir {
+        Some(dir) => match save::<T>(data, dir).await {
+            Ok(()) => {
+                info!("Saved {} games", T::NAME);
+                true
+            }
+            Err(err) => {
+                error!("Failed to save {} games: {}", T::NAME, err);
+                false
+            }
+        },
+        None => false,
+    };
+    let notice = if saved {
+        "restart-notice"
+    } else {
+        "restart-ended"
+    };
+
+    for (token, locale) in GameStore::<T>::of(data).await.live_tokens() {
+        let notice = json!({ "content": tr!(locale, notice) });
+        if let Err(err) = http
+            .create_followup_message(&token, &notice, Vec::new())
+            .await
+        {
+            warn!("Failed to tell {} players about restart: {}", T::NAME, err);
+        }
+    }
+}
+
+/// Stops new games from being started, then saves the running ones and
+/// tells their players, giving up after `GRACE_PERIOD`. Players' records are
+/// saved too.
+pub async fn finish_games(data: &RwLock<TypeMap>, http: &Http, data_dir: Option<&Path>) {
+    data.write().await.insert::<ShuttingDown>(true);
+
+    let finish_all = async {
+        finish::<MinesweeperGame>(data, http, data_dir).await;
+        finish::<TictactoeGame>(data, http, data_dir).await;
+        if let Some(dir) = data_dir {
+            if let Err(err) = records::save(data, dir).await {
+                error!("Failed to save records: {}", err);
+            }
+        }
+    };
+    if tokio::time::timeout(GRACE_PERIOD, finish_all)
+        .await
+        .is_err()
+    {
+        warn!("Stopped finishing games after {:?}", GRACE_PERIOD);
+    }
+}
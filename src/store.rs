@@ -0,0 +1,414 @@
+//! Storage for running games, shared by every game type.
+//!
+//! Each game has its own lock, so games never wait on each other. The lock is
+//! only held while a game is being changed, never while talking to Discord.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::num::ParseIntError;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+
+use dashmap::DashMap;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serenity::model::id::{InteractionId, UserId};
+use serenity::prelude::*;
+use tokio::time::Instant;
+
+use tracing::{error, info};
+
+use crate::locale::Locale;
+use crate::Error;
+
+/// How long a game can go without a move before it is dropped.
+pub const GAME_IDLE_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+
+/// How often idle games are dropped and the stores are saved.
+pub(crate) const MAINTENANCE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Held while saving, so the periodic saves and the one at shutdown never
+/// write the same file at once.
+pub(crate) static SAVING: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+/// Identifies a game by the command interaction that created its message.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub struct GameId(pub u64);
+
+impl From<InteractionId> for GameId {
+    fn from(id: InteractionId) -> GameId {
+        GameId(id.get())
+    }
+}
+
+impl GameId {
+    /// Adds the id to the current interaction's span, so everything logged
+    /// about the game can be found.
+    pub fn record(self) {
+        tracing::Span::current().record("game_id", self.0);
+    }
+}
+
+impl fmt::Display for GameId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl FromStr for GameId {
+    type Err = ParseIntError;
+
+    fn from_str(s: &str) -> Result<GameId, ParseIntError> {
+        s.parse().map(GameId)
+    }
+}
+
+/// A game that can be kept in a `GameStore`.
+pub trait Game: Clone + Serialize + DeserializeOwned + Send + Sync + 'static {
+    /// Names the game's save file.
+    const NAME: &'static str;
+
+    fn is_over(&self) -> bool;
+
+    /// Whether `user` is playing in the game.
+    fn has_player(&self, user: UserId) -> bool;
+
+    /// Token of the last interaction to update the game's message, which
+    /// can still follow up on it for a while.
+    fn token(&self) -> Option<&str>;
+
+    /// The locale of the game's message.
+    fn locale(&self) -> Locale {
+        Locale::default()
+    }
+}
+
+struct Entry<T> {
+    game: Arc<Mutex<T>>,
+    touched: Instant,
+}
+
+/// Locks `game`, even if something panicked while it was locked before.
+/// That only spoils the one game, which is better than every later look at
+/// the store panicking too.
+fn lock<T>(game: &Mutex<T>) -> MutexGuard<'_, T> {
+    game.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Running games of one type, kept in the `TypeMap` under their own type.
+/// Clones share the same games, so a store can be taken out of the `TypeMap`
+/// without keeping it locked.
+pub struct GameStore<T> {
+    games: Arc<DashMap<GameId, Entry<T>>>,
+}
+
+impl<T> Clone for GameStore<T> {
+    fn clone(&self) -> GameStore<T> {
+        GameStore {
+            games: self.games.clone(),
+        }
+    }
+}
+
+impl<T> Default for GameStore<T> {
+    fn default() -> GameStore<T> {
+        GameStore {
+            games: Arc::new(DashMap::new()),
+        }
+    }
+}
+
+impl<T: Game> TypeMapKey for GameStore<T> {
+    type Value = GameStore<T>;
+}
+
+impl<T: Game> GameStore<T> {
+    /// The store in `data`.
+    pub async fn of(data: &RwLock<TypeMap>) -> GameStore<T> {
+        data.read().await.get::<GameStore<T>>().unwrap().clone()
+    }
+
+    /// Looks up a game to play, which keeps it from expiring.
+    pub fn get(&self, id: GameId) -> Option<Arc<Mutex<T>>> {
+        self.games.get_mut(&id).map(|mut entry| {
+            entry.touched = Instant::now();
+            entry.game.clone()
+        })
+    }
+
+    /// Runs `f` on the game `id` while it is locked, returning `None` if
+    /// there is no such game. `f` can't be async, so the game can't stay
+    /// locked while a response is sent.
+    pub fn with<R>(&self, id: GameId, f: impl FnOnce(&mut T) -> R) -> Option<R> {
+        let game = self.get(id)?;
+        let mut game = lock(&game);
+        Some(f(&mut game))
+    }
+
+    pub fn insert(&self, id: GameId, game: T) {
+        self.games.insert(
+            id,
+            Entry {
+                game: Arc::new(Mutex::new(game)),
+                touched: Instant::now(),
+            },
+        );
+    }
+
+    pub fn remove(&self, id: GameId) {
+        self.games.remove(&id);
+    }
+
+    pub fn len(&self) -> usize {
+        self.games.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.games.is_empty()
+    }
+
+    /// Ids of every game.
+    pub fn ids(&self) -> Vec<GameId> {
+        self.games.iter().map(|entry| *entry.key()).collect()
+    }
+
+    /// Number of games still being played.
+    pub fn active(&self) -> usize {
+        self.snapshot()
+            .iter()
+            .filter(|(_, game)| !lock(game).is_over())
+            .count()
+    }
+
+    /// Number of games `user` is still playing.
+    pub fn active_for(&self, user: UserId) -> usize {
+        self.snapshot()
+            .iter()
+            .filter(|(_, game)| {
+                let game = lock(game);
+                !game.is_over() && game.has_player(user)
+            })
+            .count()
+    }
+
+    /// Drops games that haven't been played for `idle`, returning how many
+    /// were dropped.
+    pub fn expire(&self, idle: Duration) -> usize {
+        let before = self.games.len();
+        self.games.retain(|_, entry| entry.touched.elapsed() < idle);
+        before - self.games.len()
+    }
+
+    /// Tokens of the games still being played, with their locales.
+    pub fn live_tokens(&self) -> Vec<(String, Locale)> {
+        self.snapshot()
+            .iter()
+            .filter_map(|(_, game)| {
+                let game = lock(game);
+                match game.token() {
+                    Some(token) if !game.is_over() => Some((token.to_string(), game.locale())),
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+
+    /// Every game, taken out of the map first. Games are never locked while
+    /// the map is, so `with` can still change the store.
+    fn snapshot(&self) -> Vec<(GameId, Arc<Mutex<T>>)> {
+        self.games
+            .iter()
+            .map(|entry| (*entry.key(), entry.game.clone()))
+            .collect()
+    }
+
+    pub fn path(dir: &Path) -> PathBuf {
+        dir.join(format!("{}.json", T::NAME))
+    }
+
+    pub fn to_json(&self) -> Result<Vec<u8>, Error> {
+        let games = self
+            .snapshot()
+            .into_iter()
+            .map(|(id, game)| (id.0, lock(&game).clone()))
+            .collect::<HashMap<_, _>>();
+        Ok(serde_json::to_vec(&games)?)
+    }
+
+    pub fn from_json(json: &[u8]) -> Result<GameStore<T>, Error> {
+        let games: HashMap<u64, T> = serde_json::from_slice(json)?;
+        let store = GameStore::default();
+        for (id, game) in games {
+            store.insert(GameId(id), game);
+        }
+        Ok(store)
+    }
+
+    /// Loads the games saved in `dir`, if there are any.
+    pub fn load(dir: &Path) -> Result<GameStore<T>, Error> {
+        match std::fs::read(Self::path(dir)) {
+            Ok(json) => GameStore::from_json(&json),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(GameStore::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+/// Saves the `T` games in `data` to `dir`, replacing the previous save.
+pub async fn save<T: Game>(data: &RwLock<TypeMap>, dir: &Path) -> Result<(), Error> {
+    // Serialize first so the games aren't locked while writing
+    let json = GameStore::<T>::of(data).await.to_json()?;
+
+    let path = GameStore::<T>::path(dir);
+    let temp = path.with_extension("json.tmp");
+    let _saving = SAVING.lock().await;
+    tokio::fs::create_dir_all(dir).await?;
+    tokio::fs::write(&temp, json).await?;
+    tokio::fs::rename(&temp, &path).await?;
+    Ok(())
+}
+
+/// Periodically drops idle `T` games and saves the rest to `dir`, if set.
+pub fn maintain<T: Game>(data: Arc<RwLock<TypeMap>>, dir: Option<PathBuf>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(MAINTENANCE_INTERVAL);
+        loop {
+            interval.tick().await;
+
+            let expired = GameStore::<T>::of(&data).await.expire(GAME_IDLE_TIMEOUT);
+            if expired > 0 {
+                info!("Dropped {} idle {} games", expired, T::NAME);
+            }
+
+            if let Some(dir) = &dir {
+                if let Err(err) = save::<T>(&data, dir).await {
+                    error!("Failed to save {} games: {}", T::NAME, err);
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::panic::AssertUnwindSafe;
+
+    use super::*;
+
+    #[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+    struct TestGame {
+        over: bool,
+    }
+
+    impl Game for TestGame {
+        const NAME: &'static str = "test";
+
+        fn is_over(&self) -> bool {
+            self.over
+        }
+
+        fn has_player(&self, _user: UserId) -> bool {
+            true
+        }
+
+        fn token(&self) -> Option<&str> {
+            None
+        }
+    }
+
+    #[test]
+    fn counts_active_games() {
+        let store = GameStore::default();
+        store.insert(GameId(1), TestGame { over: false });
+        store.insert(GameId(2), TestGame { over: true });
+
+        assert_eq!(store.len(), 2);
+        assert_eq!(store.active(), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn idle_games_expire() {
+        let store = GameStore::default();
+        store.insert(GameId(1), TestGame { over: false });
+        store.insert(GameId(2), TestGame { over: false });
+
+        tokio::time::advance(Duration::from_secs(30)).await;
+        store.get(GameId(2));
+        tokio::time::advance(Duration::from_secs(40)).await;
+
+        assert_eq!(store.expire(Duration::from_secs(60)), 1);
+        assert!(store.get(GameId(1)).is_none());
+        assert!(store.get(GameId(2)).is_some());
+    }
+
+    #[test]
+    fn games_are_locked_separately() {
+        let store = GameStore::default();
+        store.insert(GameId(1), TestGame { over: false });
+        store.insert(GameId(2), TestGame { over: false });
+
+        let first = store.get(GameId(1)).unwrap();
+        let _first = first.lock().unwrap();
+
+        assert_eq!(store.with(GameId(2), |game| game.over = true), Some(()));
+        assert!(first.try_lock().is_err());
+    }
+
+    #[test]
+    fn games_are_played_in_parallel() {
+        const GAMES: u64 = 4;
+        let store = GameStore::default();
+        for id in 0..GAMES {
+            store.insert(GameId(id), TestGame { over: false });
+        }
+
+        // Every game waits for the others while locked, so this only
+        // finishes if they are all being played at once
+        let barrier = std::sync::Barrier::new(GAMES as usize);
+        std::thread::scope(|scope| {
+            for id in 0..GAMES {
+                let (store, barrier) = (&store, &barrier);
+                scope.spawn(move || {
+                    store.with(GameId(id), |game| {
+                        barrier.wait();
+                        game.over = true;
+                    })
+                });
+            }
+        });
+
+        assert_eq!(store.active(), 0);
+    }
+
+    #[test]
+    fn a_panic_spoils_only_its_game() {
+        let store = GameStore::default();
+        store.insert(GameId(1), TestGame { over: false });
+        store.insert(GameId(2), TestGame { over: false });
+
+        let panicked = std::panic::catch_unwind(AssertUnwindSafe(|| {
+            store.with(GameId(1), |_| panic!("Bug while playing"));
+        }));
+        assert!(panicked.is_err());
+
+        assert_eq!(store.active(), 2);
+        assert_eq!(store.active_for(UserId::new(10)), 2);
+        assert!(store.to_json().is_ok());
+        assert_eq!(store.with(GameId(1), |game| game.over = true), Some(()));
+    }
+
+    #[test]
+    fn survives_a_round_trip() {
+        let store = GameStore::default();
+        store.insert(GameId(1), TestGame { over: true });
+
+        let store = GameStore::<TestGame>::from_json(&store.to_json().unwrap()).unwrap();
+
+        assert_eq!(
+            store.with(GameId(1), |game| game.clone()),
+            Some(TestGame { over: true })
+        );
+    }
+}
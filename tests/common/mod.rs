@@ -0,0 +1,152 @@
+//! A stand-in for Discord's HTTP API, so interactions can be run through
+//! `Handler` end to end without a connection to Discord.
+
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server, StatusCode};
+
+use serenity::http::{Http, HttpBuilder};
+use serenity::json::{from_slice, from_str, from_value, Value};
+use serenity::model::application::Interaction;
+use serenity::model::id::ApplicationId;
+use serenity::prelude::*;
+
+/// A request made by the bot to the mock server.
+#[derive(Clone, Debug)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub path: String,
+    pub body: Value,
+}
+
+/// The application id used by the recorded interactions.
+pub const APPLICATION_ID: u64 = 1000000000000000001;
+
+/// What the bot hands each interaction, talking to a `MockDiscord`.
+#[derive(Clone)]
+pub struct TestContext {
+    pub data: Arc<RwLock<TypeMap>>,
+    pub http: Arc<Http>,
+}
+
+impl TestContext {
+    pub async fn handle(&self, interaction: Interaction) {
+        slaash::handle_interaction(&self.data, &self.http, interaction).await
+    }
+}
+
+pub struct MockDiscord {
+    addr: SocketAddr,
+    requests: Arc<Mutex<Vec<RecordedRequest>>>,
+}
+
+impl MockDiscord {
+    pub async fn start() -> MockDiscord {
+        MockDiscord::start_with_latency(Duration::ZERO).await
+    }
+
+    /// A server that takes `latency` to answer each request, like Discord
+    /// does.
+    pub async fn start_with_latency(latency: Duration) -> MockDiscord {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let recorded = requests.clone();
+        let make_service = make_service_fn(move |_| {
+            let recorded = recorded.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| {
+                    record(recorded.clone(), latency, request)
+                }))
+            }
+        });
+
+        let server = Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(make_service);
+        let addr = server.local_addr();
+        tokio::spawn(server);
+
+        MockDiscord { addr, requests }
+    }
+
+    /// A context whose HTTP client talks to this server.
+    pub fn context(&self, data: TypeMap) -> TestContext {
+        let http = HttpBuilder::new("Bot token")
+            .proxy(format!("http://{}", self.addr))
+            .ratelimiter_disabled(true)
+            .application_id(ApplicationId::new(APPLICATION_ID))
+            .build();
+
+        TestContext {
+            data: Arc::new(RwLock::new(data)),
+            http: Arc::new(http),
+        }
+    }
+
+    /// Removes and returns every request received so far.
+    pub fn take_requests(&self) -> Vec<RecordedRequest> {
+        std::mem::take(&mut *self.requests.lock().unwrap())
+    }
+
+    /// Removes and returns the only interaction callback received so far.
+    pub fn take_callback(&self) -> RecordedRequest {
+        let mut requests = self.take_requests();
+        assert_eq!(requests.len(), 1, "Expected one request: {:?}", requests);
+        let request = requests.remove(0);
+        assert_eq!(request.method, "POST");
+        assert!(
+            request.path.ends_with("/callback"),
+            "Not an interaction callback: {}",
+            request.path
+        );
+        request
+    }
+}
+
+async fn record(
+    requests: Arc<Mutex<Vec<RecordedRequest>>>,
+    latency: Duration,
+    request: Request<Body>,
+) -> Result<Response<Body>, Infallible> {
+    let method = request.method().to_string();
+    let path = request.uri().path().to_string();
+    let body = hyper::body::to_bytes(request.into_body()).await.unwrap();
+    let body = if body.is_empty() {
+        Value::Null
+    } else {
+        from_slice(&body).unwrap()
+    };
+
+    requests
+        .lock()
+        .unwrap()
+        .push(RecordedRequest { method, path, body });
+
+    tokio::time::sleep(latency).await;
+    Ok(Response::builder()
+        .status(StatusCode::NO_CONTENT)
+        .body(Body::empty())
+        .unwrap())
+}
+
+/// Loads a recorded interaction payload from `tests/fixtures`, letting the
+/// test adjust it first.
+pub fn interaction(fixture: &str, edit: impl FnOnce(&mut Value)) -> Interaction {
+    let path = format!(
+        "{}/tests/fixtures/{}.json",
+        env!("CARGO_MANIFEST_DIR"),
+        fixture
+    );
+    let mut payload: Value = from_str(std::fs::read_to_string(path).unwrap()).unwrap();
+    edit(&mut payload);
+    from_value(payload).unwrap()
+}
+
+/// Replaces the user that triggered an interaction.
+pub fn set_user(payload: &mut Value, id: u64, name: &str) {
+    let user = &mut payload["member"]["user"];
+    user["id"] = Value::from(id.to_string());
+    user["username"] = Value::from(name);
+}
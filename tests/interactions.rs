@@ -0,0 +1,405 @@
+mod common;
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serenity::json::Value;
+use serenity::prelude::*;
+
+use slaash::commands::minesweeper::MinesweeperGames;
+use slaash::commands::tictactoe::TictactoeGames;
+use slaash::ratelimit::{Limits, Rate, RateLimiter, RateLimiterContainer};
+use slaash::shutdown::finish_games;
+use slaash::store::GameStore;
+
+use common::*;
+
+const ALICE: u64 = 1000000000000000031;
+const BOB: u64 = 1000000000000000032;
+const CAROL: u64 = 1000000000000000033;
+
+fn game_stores() -> TypeMap {
+    let mut data = TypeMap::new();
+    data.insert::<MinesweeperGames>(GameStore::default());
+    data.insert::<TictactoeGames>(GameStore::default());
+    data
+}
+
+fn content(request: &RecordedRequest) -> &str {
+    request.body["data"]["content"].as_str().unwrap_or_default()
+}
+
+fn button(request: &RecordedRequest, row: usize, column: usize) -> &Value {
+    &request.body["data"]["components"][row]["components"][column]
+}
+
+async fn press(
+    discord: &MockDiscord,
+    ctx: &TestContext,
+    fixture: &str,
+    custom_id: &str,
+    user: (u64, &str),
+) -> RecordedRequest {
+    let interaction = interaction(fixture, |payload| {
+        payload["data"]["custom_id"] = Value::from(custom_id);
+        set_user(payload, user.0, user.1);
+    });
+    ctx.handle(interaction).await;
+    discord.take_callback()
+}
+
+#[tokio::test]
+async fn tictactoe_game_is_played_to_a_win() {
+    let discord = MockDiscord::start().await;
+    let ctx = discord.context(game_stores());
+
+    ctx.handle(interaction("tictactoe_command", |_| {})).await;
+    let callback = discord.take_callback();
+    assert_eq!(
+        callback.path,
+        "/api/v10/interactions/1000000000000000101/tictactoe-command-token/callback"
+    );
+    assert_eq!(callback.body["type"], 4);
+    assert_eq!(
+        content(&callback),
+        format!(
+            "<@{}> has started a game of tic-tac-toe! Who would like to play?",
+            ALICE
+        )
+    );
+    assert_eq!(button(&callback, 0, 0)["custom_id"], "tictactoe-join");
+
+    let callback = press(
+        &discord,
+        &ctx,
+        "tictactoe_button",
+        "tictactoe-join",
+        (BOB, "bob"),
+    )
+    .await;
+    assert_eq!(callback.body["type"], 7);
+    assert!(content(&callback).starts_with(&format!("<@{}>'s turn!\nMake your move <t:", ALICE)));
+    assert_eq!(
+        callback.body["data"]["components"]
+            .as_array()
+            .unwrap()
+            .len(),
+        4
+    );
+
+    let moves = [
+        (0, (ALICE, "alice")),
+        (3, (BOB, "bob")),
+        (1, (ALICE, "alice")),
+        (4, (BOB, "bob")),
+    ];
+    for (cell, user) in moves {
+        let custom_id = format!("tictactoe-game-{}", cell);
+        press(&discord, &ctx, "tictactoe_button", &custom_id, user).await;
+    }
+
+    let callback = press(
+        &discord,
+        &ctx,
+        "tictactoe_button",
+        "tictactoe-game-2",
+        (ALICE, "alice"),
+    )
+    .await;
+    assert!(content(&callback).starts_with(&format!("<@{}> won!", ALICE)));
+    assert_eq!(button(&callback, 0, 2)["style"], 3);
+    assert_eq!(button(&callback, 2, 2)["disabled"], true);
+    assert_eq!(button(&callback, 3, 0)["custom_id"], "tictactoe-rematch");
+}
+
+#[tokio::test]
+async fn tictactoe_outsiders_cant_play() {
+    let discord = MockDiscord::start().await;
+    let ctx = discord.context(game_stores());
+
+    ctx.handle(interaction("tictactoe_command", |_| {})).await;
+    discord.take_callback();
+    press(
+        &discord,
+        &ctx,
+        "tictactoe_button",
+        "tictactoe-join",
+        (BOB, "bob"),
+    )
+    .await;
+
+    let callback = press(
+        &discord,
+        &ctx,
+        "tictactoe_button",
+        "tictactoe-game-0",
+        (CAROL, "carol"),
+    )
+    .await;
+    assert_eq!(callback.body["type"], 4);
+    assert_eq!(callback.body["data"]["flags"], 64);
+    assert!(content(&callback).starts_with("That's not your game!"));
+}
+
+/// Plays a game of tic-tac-toe in a message created by the command
+/// interaction `id`, which Alice wins.
+async fn play_tictactoe(ctx: TestContext, id: u64) {
+    let command = interaction("tictactoe_command", |payload| {
+        payload["id"] = Value::from(id.to_string());
+    });
+    ctx.handle(command).await;
+
+    let presses = [
+        ("tictactoe-join", (BOB, "bob")),
+        ("tictactoe-game-0", (ALICE, "alice")),
+        ("tictactoe-game-3", (BOB, "bob")),
+        ("tictactoe-game-1", (ALICE, "alice")),
+        ("tictactoe-game-4", (BOB, "bob")),
+        ("tictactoe-game-2", (ALICE, "alice")),
+    ];
+    for (custom_id, user) in presses {
+        let press = interaction("tictactoe_button", |payload| {
+            payload["data"]["custom_id"] = Value::from(custom_id);
+            payload["message"]["interaction_metadata"]["id"] = Value::from(id.to_string());
+            set_user(payload, user.0, user.1);
+        });
+        ctx.handle(press).await;
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn tictactoe_games_progress_in_parallel() {
+    const GAMES: u64 = 20;
+    const REQUESTS_PER_GAME: u32 = 7;
+    const LATENCY: Duration = Duration::from_millis(100);
+
+    let discord = MockDiscord::start_with_latency(LATENCY).await;
+    let ctx = discord.context(game_stores());
+
+    let started = Instant::now();
+    let games = (0..GAMES).map(|game| tokio::spawn(play_tictactoe(ctx.clone(), 2000 + game)));
+    for game in futures::future::join_all(games).await {
+        game.unwrap();
+    }
+    let elapsed = started.elapsed();
+
+    // One game at a time would take GAMES * REQUESTS_PER_GAME * LATENCY
+    assert!(
+        elapsed < LATENCY * REQUESTS_PER_GAME * 3,
+        "Games took {:?}",
+        elapsed
+    );
+    assert_eq!(
+        discord.take_requests().len(),
+        (GAMES * REQUESTS_PER_GAME as u64) as usize
+    );
+    let games = TictactoeGames::of(&ctx.data).await;
+    assert_eq!(games.len(), GAMES as usize);
+    assert_eq!(games.active(), 0);
+}
+
+#[tokio::test]
+async fn minesweeper_first_click_reveals_cell() {
+    let discord = MockDiscord::start().await;
+    let ctx = discord.context(game_stores());
+
+    ctx.handle(interaction("minesweeper_command", |_| {})).await;
+    let callback = discord.take_callback();
+    assert_eq!(callback.body["type"], 4);
+    let rows = callback.body["data"]["components"].as_array().unwrap();
+    assert_eq!(rows.len(), 5);
+    assert_eq!(
+        button(&callback, 2, 2)["custom_id"],
+        "minesweeper-1000000000000000201-12"
+    );
+
+    let callback = press(
+        &discord,
+        &ctx,
+        "minesweeper_button",
+        "minesweeper-1000000000000000201-12",
+        (ALICE, "alice"),
+    )
+    .await;
+    assert_eq!(
+        callback.path,
+        "/api/v10/interactions/1000000000000000202/minesweeper-button-token/callback"
+    );
+    assert_eq!(callback.body["type"], 7);
+    assert_eq!(button(&callback, 2, 2)["disabled"], true);
+    assert_eq!(button(&callback, 2, 2)["style"], 3);
+}
+
+#[tokio::test]
+async fn minesweeper_rejects_other_players() {
+    let discord = MockDiscord::start().await;
+    let ctx = discord.context(game_stores());
+
+    ctx.handle(interaction("minesweeper_command", |_| {})).await;
+    discord.take_callback();
+
+    let callback = press(
+        &discord,
+        &ctx,
+        "minesweeper_button",
+        "minesweeper-1000000000000000201-12",
+        (BOB, "bob"),
+    )
+    .await;
+    assert_eq!(callback.body["data"]["flags"], 64);
+    assert!(content(&callback).starts_with("Thats not your game!"));
+}
+
+#[tokio::test]
+async fn shutdown_saves_games_and_refuses_new_ones() {
+    let discord = MockDiscord::start().await;
+    let ctx = discord.context(game_stores());
+    let dir = std::env::temp_dir().join(format!("slaash-shutdown-{}", std::process::id()));
+
+    ctx.handle(interaction("tictactoe_command", |_| {})).await;
+    ctx.handle(interaction("minesweeper_command", |_| {})).await;
+    discord.take_requests();
+
+    finish_games(&ctx.data, &ctx.http, Some(&dir)).await;
+
+    let mut paths = discord
+        .take_requests()
+        .into_iter()
+        .map(|request| {
+            assert_eq!(
+                request.body["content"],
+                "Bot restarting, your game will resume shortly."
+            );
+            request.path
+        })
+        .collect::<Vec<_>>();
+    paths.sort();
+    assert_eq!(
+        paths,
+        [
+            "/api/v10/webhooks/1000000000000000001/minesweeper-command-token",
+            "/api/v10/webhooks/1000000000000000001/tictactoe-command-token"
+        ]
+    );
+    assert!(dir.join("minesweeper.json").exists());
+    assert!(dir.join("tictactoe.json").exists());
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    ctx.handle(interaction("tictactoe_command", |_| {})).await;
+    let callback = discord.take_callback();
+    assert_eq!(callback.body["data"]["flags"], 64);
+    assert!(content(&callback).starts_with("The bot is restarting"));
+
+    // Buttons that would start a game are refused too
+    let callback = press(
+        &discord,
+        &ctx,
+        "tictactoe_button",
+        "tictactoe-join",
+        (BOB, "bob"),
+    )
+    .await;
+    assert_eq!(callback.body["data"]["flags"], 64);
+    assert!(content(&callback).starts_with("The bot is restarting"));
+}
+
+#[tokio::test]
+async fn shutdown_without_saving_ends_games() {
+    let discord = MockDiscord::start().await;
+    let ctx = discord.context(game_stores());
+
+    ctx.handle(interaction("tictactoe_command", |_| {})).await;
+    discord.take_requests();
+
+    finish_games(&ctx.data, &ctx.http, None).await;
+
+    let requests = discord.take_requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(
+        requests[0].body["content"],
+        "Bot restarting, your game couldn't be saved and has ended."
+    );
+}
+
+#[tokio::test]
+async fn failed_commands_reply_with_error() {
+    let discord = MockDiscord::start().await;
+    let ctx = discord.context(game_stores());
+
+    let interaction = interaction("minesweeper_command", |payload| {
+        payload["data"]["name"] = Value::from("unknown");
+    });
+    ctx.handle(interaction).await;
+
+    let callback = discord.take_callback();
+    assert_eq!(callback.body["data"]["flags"], 64);
+    assert!(content(&callback).contains("Command not implemented"));
+}
+
+#[tokio::test]
+async fn spamming_commands_is_throttled() {
+    let discord = MockDiscord::start().await;
+    let mut data = game_stores();
+    data.insert::<RateLimiterContainer>(Arc::new(RateLimiter::new(Limits {
+        commands: Rate {
+            burst: 2,
+            per: Duration::from_secs(60),
+        },
+        games_per_user: 1,
+        ..Limits::default()
+    })));
+    let ctx = discord.context(data);
+
+    ctx.handle(interaction("minesweeper_command", |_| {})).await;
+    assert_eq!(discord.take_callback().body["type"], 4);
+
+    ctx.handle(interaction("minesweeper_command", |_| {})).await;
+    let callback = discord.take_callback();
+    assert_eq!(callback.body["data"]["flags"], 64);
+    assert!(content(&callback).starts_with("You already have too many games going"));
+
+    ctx.handle(interaction("minesweeper_command", |_| {})).await;
+    let callback = discord.take_callback();
+    assert_eq!(callback.body["data"]["flags"], 64);
+    assert_eq!(content(&callback), "Slow down! Try again in 30 seconds.");
+    assert_eq!(MinesweeperGames::of(&ctx.data).await.len(), 1);
+}
+
+#[tokio::test]
+async fn joining_counts_towards_the_game_cap() {
+    let discord = MockDiscord::start().await;
+    let mut data = game_stores();
+    data.insert::<RateLimiterContainer>(Arc::new(RateLimiter::new(Limits {
+        games_per_user: 1,
+        ..Limits::default()
+    })));
+    let ctx = discord.context(data);
+
+    let bobs_game = interaction("minesweeper_command", |payload| {
+        set_user(payload, BOB, "bob");
+    });
+    ctx.handle(bobs_game).await;
+    ctx.handle(interaction("tictactoe_command", |_| {})).await;
+    discord.take_requests();
+
+    let callback = press(
+        &discord,
+        &ctx,
+        "tictactoe_button",
+        "tictactoe-join",
+        (BOB, "bob"),
+    )
+    .await;
+    assert_eq!(callback.body["data"]["flags"], 64);
+    assert!(content(&callback).starts_with("You already have too many games going"));
+
+    let callback = press(
+        &discord,
+        &ctx,
+        "tictactoe_button",
+        "tictactoe-join",
+        (CAROL, "carol"),
+    )
+    .await;
+    assert_eq!(callback.body["type"], 7);
+}